@@ -0,0 +1,383 @@
+use std::collections;
+use std::io;
+
+extern crate anyhow;
+
+#[derive(Debug, Clone)]
+pub struct Cell {
+    pub num: u32,
+    pub is_marked: bool,
+}
+
+/// A square bingo board of `len` by `len` cells, stored row by row. Marks are also counted per
+/// row, column and diagonal so wins can be checked without rescanning the grid.
+#[derive(Debug, Clone)]
+pub struct Board {
+    len: usize,
+    grid: Vec<Cell>,
+    num_idxs: collections::HashMap<u32, usize>,
+    row_marks: Vec<usize>,
+    col_marks: Vec<usize>,
+    diag_marks: [usize; 2],
+    num_marked: usize,
+    unmarked_sum: u64,
+}
+
+pub trait WinCondition {
+    /// Called after cell `idx` of `board` is marked, returning the indices into the board's grid
+    /// of the cells completing a win if marking it won the board.
+    fn find_win(&self, board: &Board, idx: usize) -> Option<Vec<usize>>;
+}
+
+pub struct Lines;
+
+pub struct Diagonals;
+
+pub struct FullCard;
+
+#[derive(Debug, Clone)]
+pub struct Win {
+    pub board: usize,
+    pub draw: usize,
+    pub num: u32,
+    pub cells: Vec<usize>,
+    pub score: u64,
+}
+
+pub struct Bingo {
+    nums: Vec<u32>,
+    boards: Vec<Board>,
+}
+
+impl Board {
+    pub fn new(len: usize, nums: Vec<u32>) -> anyhow::Result<Self> {
+        if len == 0 || nums.len() != len * len {
+            return Err(anyhow::anyhow!(
+                "Expected {} numbers for a {}x{} board, got {}!",
+                len * len,
+                len,
+                len,
+                nums.len()
+            ));
+        }
+
+        let mut num_idxs = collections::HashMap::new();
+
+        for (idx, &num) in nums.iter().enumerate() {
+            if let Some(first_idx) = num_idxs.insert(num, idx) {
+                return Err(anyhow::anyhow!(
+                    "Number {} appears at both row {}, column {} and row {}, column {}!",
+                    num,
+                    first_idx / len + 1,
+                    first_idx % len + 1,
+                    idx / len + 1,
+                    idx % len + 1
+                ));
+            }
+        }
+
+        let unmarked_sum = nums.iter().map(|&num| num as u64).sum();
+
+        let grid = nums
+            .into_iter()
+            .map(|num| Cell {
+                num,
+                is_marked: false,
+            })
+            .collect();
+
+        Ok(Self {
+            len,
+            grid,
+            num_idxs,
+            row_marks: vec![0; len],
+            col_marks: vec![0; len],
+            diag_marks: [0; 2],
+            num_marked: 0,
+            unmarked_sum,
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.len
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> &Cell {
+        &self.grid[i * self.len + j]
+    }
+
+    pub fn cells(&self) -> &[Cell] {
+        &self.grid
+    }
+
+    pub fn mark(&mut self, num: u32) -> Option<usize> {
+        let idx = *self.num_idxs.get(&num)?;
+        self.mark_cell(idx);
+
+        Some(idx)
+    }
+
+    pub fn mark_cell(&mut self, idx: usize) -> bool {
+        let cell = &mut self.grid[idx];
+
+        if cell.is_marked {
+            return false;
+        }
+
+        cell.is_marked = true;
+        self.unmarked_sum -= cell.num as u64;
+        self.num_marked += 1;
+
+        let (i, j) = (idx / self.len, idx % self.len);
+
+        self.row_marks[i] += 1;
+        self.col_marks[j] += 1;
+
+        if i == j {
+            self.diag_marks[0] += 1;
+        }
+
+        if i + j == self.len - 1 {
+            self.diag_marks[1] += 1;
+        }
+
+        true
+    }
+
+    pub fn unmarked_sum(&self) -> u64 {
+        self.unmarked_sum
+    }
+
+    pub fn get_score(&self, winning_num: u32) -> u64 {
+        self.unmarked_sum * winning_num as u64
+    }
+
+    pub fn render(&self, highlight: &[usize]) -> String {
+        let width = self
+            .grid
+            .iter()
+            .map(|cell| cell.num.to_string().len())
+            .max()
+            .unwrap_or(0);
+
+        (0..self.len)
+            .map(|i| {
+                (0..self.len)
+                    .map(|j| {
+                        let cell = self.get(i, j);
+
+                        match (highlight.contains(&(i * self.len + j)), cell.is_marked) {
+                            (true, _) => format!("<{:>width$}>", cell.num, width = width),
+                            (false, true) => format!("[{:>width$}]", cell.num, width = width),
+                            (false, false) => format!(" {:>width$} ", cell.num, width = width),
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn row(&self, i: usize) -> Vec<usize> {
+        (0..self.len).map(|j| i * self.len + j).collect()
+    }
+
+    fn col(&self, j: usize) -> Vec<usize> {
+        (0..self.len).map(|i| i * self.len + j).collect()
+    }
+
+    fn find_line(&self, idx: usize) -> Option<Vec<usize>> {
+        let (i, j) = (idx / self.len, idx % self.len);
+
+        if self.row_marks[i] == self.len {
+            Some(self.row(i))
+        } else if self.col_marks[j] == self.len {
+            Some(self.col(j))
+        } else {
+            None
+        }
+    }
+
+    fn find_diagonal(&self, idx: usize) -> Option<Vec<usize>> {
+        let (i, j) = (idx / self.len, idx % self.len);
+
+        if i == j && self.diag_marks[0] == self.len {
+            Some((0..self.len).map(|k| k * self.len + k).collect())
+        } else if i + j == self.len - 1 && self.diag_marks[1] == self.len {
+            Some(
+                (0..self.len)
+                    .map(|k| k * self.len + self.len - 1 - k)
+                    .collect(),
+            )
+        } else {
+            None
+        }
+    }
+}
+
+impl WinCondition for Lines {
+    fn find_win(&self, board: &Board, idx: usize) -> Option<Vec<usize>> {
+        board.find_line(idx)
+    }
+}
+
+impl WinCondition for Diagonals {
+    fn find_win(&self, board: &Board, idx: usize) -> Option<Vec<usize>> {
+        board.find_line(idx).or_else(|| board.find_diagonal(idx))
+    }
+}
+
+impl WinCondition for FullCard {
+    fn find_win(&self, board: &Board, _: usize) -> Option<Vec<usize>> {
+        match board.num_marked == board.grid.len() {
+            true => Some((0..board.grid.len()).collect()),
+            false => None,
+        }
+    }
+}
+
+impl Bingo {
+    pub fn new(nums: Vec<u32>, boards: Vec<Board>) -> Self {
+        Self { nums, boards }
+    }
+
+    pub fn read(reader: impl io::BufRead) -> anyhow::Result<Self> {
+        let mut lines = reader.lines().enumerate();
+
+        let nums = lines
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Empty drawn numbers input!"))?
+            .1?
+            .split(',')
+            .map(|num| {
+                num.trim()
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Line 1: invalid drawn number '{}'!", num))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let mut boards = vec![];
+        let mut rows = vec![];
+
+        for (idx, line) in lines {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                if !rows.is_empty() {
+                    boards.push(Self::parse_board(&rows)?);
+                    rows.clear();
+                }
+
+                continue;
+            }
+
+            let row = line
+                .split_whitespace()
+                .map(|num| {
+                    num.parse().map_err(|_| {
+                        anyhow::anyhow!("Line {}: invalid board number '{}'!", idx + 1, num)
+                    })
+                })
+                .collect::<anyhow::Result<Vec<u32>>>()?;
+
+            rows.push((idx + 1, row));
+        }
+
+        if !rows.is_empty() {
+            boards.push(Self::parse_board(&rows)?);
+        }
+
+        if boards.is_empty() {
+            return Err(anyhow::anyhow!("No boards given!"));
+        }
+
+        Ok(Self::new(nums, boards))
+    }
+
+    fn parse_board(rows: &[(usize, Vec<u32>)]) -> anyhow::Result<Board> {
+        let (first_line_no, len) = rows
+            .first()
+            .map(|(line_no, row)| (*line_no, row.len()))
+            .ok_or_else(|| anyhow::anyhow!("Unexpected empty board input!"))?;
+
+        for (line_no, row) in rows {
+            if row.len() != len {
+                return Err(anyhow::anyhow!(
+                    "Line {}: expected {} numbers like the board's first row, got {}!",
+                    line_no,
+                    len,
+                    row.len()
+                ));
+            }
+        }
+
+        if rows.len() != len {
+            return Err(anyhow::anyhow!(
+                "Line {}: board has {} rows of {} numbers, expected a square board!",
+                first_line_no,
+                rows.len(),
+                len
+            ));
+        }
+
+        Board::new(len, rows.iter().flat_map(|(_, row)| row).copied().collect())
+            .map_err(|err| err.context(format!("Board starting at line {}", first_line_no)))
+    }
+
+    pub fn nums(&self) -> &[u32] {
+        &self.nums
+    }
+
+    pub fn boards(&self) -> &[Board] {
+        &self.boards
+    }
+
+    /// Plays every draw, returning each board's win in the order they happen. Boards winning on
+    /// the same draw are ordered by index, and boards that never win are left out.
+    ///
+    /// Each number is looked up in an index of the cells it appears in, so a draw only touches
+    /// the boards containing it.
+    pub fn play(&self, win_condition: &dyn WinCondition) -> Vec<Win> {
+        let mut cells = collections::HashMap::<_, Vec<_>>::new();
+
+        for (board_idx, board) in self.boards.iter().enumerate() {
+            for (idx, cell) in board.grid.iter().enumerate() {
+                cells.entry(cell.num).or_default().push((board_idx, idx));
+            }
+        }
+
+        let mut boards = self.boards.clone();
+        let mut has_won = vec![false; boards.len()];
+        let mut wins = vec![];
+
+        for (draw, &num) in self.nums.iter().enumerate() {
+            if wins.len() == boards.len() {
+                break;
+            }
+
+            for &(board_idx, idx) in cells.get(&num).into_iter().flatten() {
+                let board = &mut boards[board_idx];
+
+                if has_won[board_idx] || !board.mark_cell(idx) {
+                    continue;
+                }
+
+                if let Some(cells) = win_condition.find_win(board, idx) {
+                    has_won[board_idx] = true;
+                    wins.push(Win {
+                        board: board_idx,
+                        draw,
+                        num,
+                        cells,
+                        score: board.get_score(num),
+                    });
+                }
+            }
+        }
+
+        wins
+    }
+}
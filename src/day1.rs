@@ -1,50 +1,259 @@
+use std::cmp;
+use std::env;
+use std::str;
+
 extern crate anyhow;
 
 use aoc2021_rust::util;
 
-const PART_ONE_WINDOW_LEN: usize = 2;
+const PART_ONE_WINDOW_LEN: usize = 1;
 const PART_TWO_WINDOW_LEN: usize = 3;
 
-fn part_one(depths: &[isize]) -> anyhow::Result<usize> {
-    if depths.is_empty() {
-        return Err(anyhow::anyhow!("No depth readings given!"));
+/// Window whose sums are checked for sharp changes in the depth report.
+const REPORT_WINDOW_LEN: usize = PART_TWO_WINDOW_LEN;
+
+/// Multiple of the mean absolute window-sum change above which a change counts as sharp, unless
+/// a threshold is given explicitly.
+const SHARP_CHANGE_FACTOR: isize = 3;
+
+#[derive(Clone, Copy)]
+enum ReportFormat {
+    Text,
+    Json,
+}
+
+struct DepthReport {
+    num_readings: usize,
+    num_decreases: usize,
+    /// Start index and number of readings of the longest strictly increasing run.
+    longest_run: (usize, usize),
+    /// Index and signed change of the largest absolute change between consecutive readings.
+    largest_jump: Option<(usize, isize)>,
+    threshold: isize,
+    /// Start index and signed change of every window sum differing from the previous one by at
+    /// least `threshold`.
+    sharp_changes: Vec<(usize, isize)>,
+}
+
+impl str::FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(format: &str) -> anyhow::Result<Self> {
+        match format {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(anyhow::anyhow!(
+                "Unknown report format '{}', expected 'text' or 'json'!",
+                format
+            )),
+        }
     }
+}
 
-    if depths.len() < PART_ONE_WINDOW_LEN {
-        return Ok(0);
+impl DepthReport {
+    fn new(depths: &[isize], threshold: Option<isize>) -> anyhow::Result<Self> {
+        if depths.is_empty() {
+            return Err(anyhow::anyhow!("No depth readings given!"));
+        }
+
+        let mut longest_run = (0, 1);
+        let mut run_start = 0;
+
+        for (idx, pair) in depths.windows(2).enumerate() {
+            if pair[0] >= pair[1] {
+                run_start = idx + 1;
+            } else if idx + 2 - run_start > longest_run.1 {
+                longest_run = (run_start, idx + 2 - run_start);
+            }
+        }
+
+        let largest_jump = depths
+            .windows(2)
+            .enumerate()
+            .map(|(idx, pair)| (idx + 1, pair[1] - pair[0]))
+            .max_by_key(|&(idx, change)| (change.abs(), cmp::Reverse(idx)));
+
+        let window_changes = depths
+            .iter()
+            .zip(depths.iter().skip(REPORT_WINDOW_LEN))
+            .enumerate()
+            .map(|(idx, (first, last))| (idx + 1, last - first))
+            .collect::<Vec<_>>();
+
+        let threshold = threshold.unwrap_or_else(|| {
+            let num_changes = cmp::max(window_changes.len(), 1) as isize;
+            let total = window_changes
+                .iter()
+                .map(|(_, change)| change.abs())
+                .sum::<isize>();
+
+            cmp::max(SHARP_CHANGE_FACTOR * total / num_changes, 1)
+        });
+
+        Ok(Self {
+            num_readings: depths.len(),
+            num_decreases: depths.windows(2).filter(|pair| pair[0] > pair[1]).count(),
+            longest_run,
+            largest_jump,
+            threshold,
+            sharp_changes: window_changes
+                .into_iter()
+                .filter(|(_, change)| change.abs() >= threshold)
+                .collect(),
+        })
     }
 
-    Ok(depths
-        .windows(PART_ONE_WINDOW_LEN)
-        .filter(|&window| window[0] < window[1])
-        .collect::<Vec<_>>()
-        .len())
+    fn to_string(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Text => {
+                let mut out = format!("Readings: {}\n", self.num_readings);
+
+                out += &format!("Decreases: {}\n", self.num_decreases);
+                out += &format!(
+                    "Longest increasing run: {} readings from index {}\n",
+                    self.longest_run.1, self.longest_run.0
+                );
+
+                match self.largest_jump {
+                    Some((idx, change)) => {
+                        out += &format!("Largest jump: {:+} at index {}\n", change, idx)
+                    }
+                    None => out += "Largest jump: none\n",
+                }
+
+                out += &format!(
+                    "Sharp changes in {}-reading window sums (threshold {}): {}\n",
+                    REPORT_WINDOW_LEN,
+                    self.threshold,
+                    self.sharp_changes.len()
+                );
+
+                for (idx, change) in self.sharp_changes.iter() {
+                    out += &format!("  {:+} at index {}\n", change, idx);
+                }
+
+                out
+            }
+            ReportFormat::Json => {
+                let largest_jump = match self.largest_jump {
+                    Some((idx, change)) => {
+                        format!("{{\"index\": {}, \"change\": {}}}", idx, change)
+                    }
+                    None => String::from("null"),
+                };
+
+                let sharp_changes = self
+                    .sharp_changes
+                    .iter()
+                    .map(|(idx, change)| format!("{{\"index\": {}, \"change\": {}}}", idx, change))
+                    .collect::<Vec<_>>();
+
+                format!(
+                    "{{\"readings\": {}, \"decreases\": {}, \"longest_run\": {{\"index\": {}, \"len\": {}}}, \"largest_jump\": {}, \"window_len\": {}, \"threshold\": {}, \"sharp_changes\": [{}]}}\n",
+                    self.num_readings,
+                    self.num_decreases,
+                    self.longest_run.0,
+                    self.longest_run.1,
+                    largest_jump,
+                    REPORT_WINDOW_LEN,
+                    self.threshold,
+                    sharp_changes.join(", ")
+                )
+            }
+        }
+    }
 }
 
-fn part_two(depths: &[isize]) -> anyhow::Result<usize> {
+/// Counts how often the sum of a sliding window of `window_len` depths increases. Consecutive
+/// windows share all but their first and last depths, so comparing the sums reduces to
+/// comparing `depths[i]` with `depths[i + window_len]`.
+fn count_increases(depths: &[isize], window_len: usize) -> anyhow::Result<usize> {
     if depths.is_empty() {
         return Err(anyhow::anyhow!("No depth readings given!"));
     }
 
-    if depths.len() < PART_TWO_WINDOW_LEN * PART_ONE_WINDOW_LEN {
-        return Ok(0);
+    if window_len == 0 {
+        return Err(anyhow::anyhow!("Window length must be positive!"));
     }
 
     Ok(depths
-        .windows(PART_TWO_WINDOW_LEN)
-        .map(|window| window.iter().sum::<isize>())
-        .collect::<Vec<_>>()
-        .windows(PART_ONE_WINDOW_LEN)
-        .filter(|&window| window[0] < window[1])
-        .collect::<Vec<_>>()
-        .len())
+        .iter()
+        .zip(depths.iter().skip(window_len))
+        .filter(|&(first, last)| first < last)
+        .count())
+}
+
+fn part_one(depths: &[isize]) -> anyhow::Result<usize> {
+    count_increases(depths, PART_ONE_WINDOW_LEN)
+}
+
+fn part_two(depths: &[isize]) -> anyhow::Result<usize> {
+    count_increases(depths, PART_TWO_WINDOW_LEN)
 }
 
 fn main() -> anyhow::Result<()> {
+    let mut args = env::args().skip(1);
+    let mut window_lens = vec![];
+    let mut report = None;
+    let mut threshold = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--window" => window_lens.push(
+                args.next()
+                    .ok_or_else(|| anyhow::anyhow!("Missing window length!"))?
+                    .parse::<usize>()?,
+            ),
+            "--report" => {
+                report = Some(
+                    args.next()
+                        .ok_or_else(|| anyhow::anyhow!("Missing report format!"))?
+                        .parse::<ReportFormat>()?,
+                )
+            }
+            "--threshold" => {
+                threshold = Some(
+                    args.next()
+                        .ok_or_else(|| anyhow::anyhow!("Missing sharp change threshold!"))?
+                        .parse::<isize>()?,
+                );
+
+                if threshold <= Some(0) {
+                    return Err(anyhow::anyhow!("Sharp change threshold must be positive!"));
+                }
+            }
+            _ => return Err(anyhow::anyhow!("Unknown argument '{}'!", arg)),
+        }
+    }
+
     let input = util::read_input::<isize>()?;
 
-    println!("Part one: {}", part_one(&input)?);
-    println!("Part two: {}", part_two(&input)?);
+    if let Some(format) = report {
+        if !window_lens.is_empty() {
+            return Err(anyhow::anyhow!(
+                "'--window' cannot be used with '--report'!"
+            ));
+        }
+
+        print!("{}", DepthReport::new(&input, threshold)?.to_string(format));
+
+        return Ok(());
+    } else if threshold.is_some() {
+        return Err(anyhow::anyhow!("A threshold requires '--report'!"));
+    }
+
+    if window_lens.is_empty() {
+        println!("Part one: {}", part_one(&input)?);
+        println!("Part two: {}", part_two(&input)?);
+    }
+
+    for window_len in window_lens {
+        println!(
+            "Window {}: {}",
+            window_len,
+            count_increases(&input, window_len)?
+        );
+    }
 
     Ok(())
 }
@@ -1,62 +1,46 @@
 extern crate anyhow;
 
 use std::cmp;
-use std::io::{self, BufRead};
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::fmt;
 use std::ops;
+use std::str;
+
+use aoc2021_rust::util;
+
+const CSV_HEADER: &str = "area,x_vel,y_vel,apex,flight,entry_x,entry_y";
 
 struct Area {
-    x_rng: ops::RangeInclusive<isize>,
-    y_rng: ops::RangeInclusive<isize>,
     x_min: isize,
     x_max: isize,
     y_min: isize,
     y_max: isize,
 }
 
-impl Area {
-    fn read() -> anyhow::Result<Self> {
-        let line = io::stdin()
-            .lock()
-            .lines()
-            .next()
-            .ok_or_else(|| anyhow::anyhow!("Missing target area input!"))??;
-
-        let mut split = line["target area: ".len()..].split(", ");
-
-        let mut x_split = split
-            .next()
-            .ok_or_else(|| anyhow::anyhow!("Missing x-direction target range!"))?["x=".len()..]
-            .split("..");
-
-        let mut y_split = split
-            .next()
-            .ok_or_else(|| anyhow::anyhow!("Missing y-direction target range!"))?["y=".len()..]
-            .split("..");
-
-        let x_rng = x_split
-            .next()
-            .ok_or_else(|| anyhow::anyhow!("Missing x-direction target range lower bound!"))?
-            .parse()?
-            ..=x_split
-                .next()
-                .ok_or_else(|| anyhow::anyhow!("Missing x-direction target range upper bound!"))?
-                .parse()?;
-
-        let y_rng = y_split
-            .next()
-            .ok_or_else(|| anyhow::anyhow!("Missing y-direction target range lower bound!"))?
-            .parse()?
-            ..=y_split
-                .next()
-                .ok_or_else(|| anyhow::anyhow!("Missing y-direction target range upper bound!"))?
-                .parse()?;
-
-        let (x_min, x_max) = (x_rng.clone().min().unwrap(), x_rng.clone().max().unwrap());
-        let (y_min, y_max) = (y_rng.clone().min().unwrap(), y_rng.clone().max().unwrap());
+struct Trajectory {
+    positions: Vec<(isize, isize)>,
+    entry_step: Option<usize>,
+}
+
+impl str::FromStr for Area {
+    type Err = anyhow::Error;
+
+    fn from_str(line: &str) -> anyhow::Result<Self> {
+        let ranges = line
+            .trim()
+            .strip_prefix("target area")
+            .and_then(|ranges| ranges.trim_start().strip_prefix(':'))
+            .ok_or_else(|| anyhow::anyhow!("Missing 'target area:' prefix!"))?;
+
+        let (x_rng, y_rng) = ranges
+            .split_once(',')
+            .ok_or_else(|| anyhow::anyhow!("Expected x- and y-direction target ranges!"))?;
+
+        let (x_min, x_max) = parse_rng(x_rng, "x")?;
+        let (y_min, y_max) = parse_rng(y_rng, "y")?;
 
         Ok(Area {
-            x_rng,
-            y_rng,
             x_min,
             x_max,
             y_min,
@@ -65,57 +49,416 @@ impl Area {
     }
 }
 
-fn trick_shot(mut vel: (isize, isize), area: &Area) -> bool {
-    let mut pos = (0, 0);
+impl fmt::Display for Area {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            fmt,
+            "target area: x={}..{}, y={}..{}",
+            self.x_min, self.x_max, self.y_min, self.y_max
+        )
+    }
+}
+
+fn parse_rng(rng: &str, axis: &str) -> anyhow::Result<(isize, isize)> {
+    let bounds = rng
+        .trim()
+        .strip_prefix(axis)
+        .and_then(|bounds| bounds.trim_start().strip_prefix('='))
+        .ok_or_else(|| {
+            anyhow::anyhow!("Missing '{}=' in {}-direction target range!", axis, axis)
+        })?;
+
+    let (from, to) = bounds
+        .split_once("..")
+        .ok_or_else(|| anyhow::anyhow!("Expected '..' in {}-direction target range!", axis))?;
+
+    let from = from.trim().parse::<isize>()?;
+    let to = to.trim().parse::<isize>()?;
+
+    Ok((cmp::min(from, to), cmp::max(from, to)))
+}
+
+impl Area {
+    fn read_all() -> anyhow::Result<Vec<Self>> {
+        let areas = util::read_lines::<Self>()?;
+
+        match areas.is_empty() {
+            true => Err(anyhow::anyhow!("Missing target area input!")),
+            false => Ok(areas),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Shot {
+    vel: (isize, isize),
+    apex: isize,
+    flight: usize,
+    entry: (isize, isize),
+}
+
+#[derive(Clone, Copy)]
+enum Mode {
+    Solve,
+    Plot((isize, isize)),
+    Csv(ShotOrder),
+}
+
+#[derive(Clone, Copy)]
+enum ShotOrder {
+    Apex,
+    Flight,
+    Entry,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum ShotKey {
+    Apex(isize),
+    Flight(usize),
+    Entry((isize, isize)),
+}
+
+impl str::FromStr for ShotOrder {
+    type Err = anyhow::Error;
+
+    fn from_str(order: &str) -> anyhow::Result<Self> {
+        match order {
+            "apex" => Ok(Self::Apex),
+            "flight" => Ok(Self::Flight),
+            "entry" => Ok(Self::Entry),
+            _ => Err(anyhow::anyhow!(
+                "Unknown shot order '{}', expected 'apex', 'flight' or 'entry'!",
+                order
+            )),
+        }
+    }
+}
+
+impl ShotOrder {
+    fn key(self, shot: &Shot) -> ShotKey {
+        match self {
+            Self::Apex => ShotKey::Apex(shot.apex),
+            Self::Flight => ShotKey::Flight(shot.flight),
+            Self::Entry => ShotKey::Entry(shot.entry),
+        }
+    }
+
+    fn group(self, shots: &[Shot]) -> BTreeMap<ShotKey, Vec<Shot>> {
+        let mut groups = BTreeMap::<_, Vec<_>>::new();
+
+        for &shot in shots {
+            groups.entry(self.key(&shot)).or_default().push(shot);
+        }
+
+        groups.values_mut().for_each(|group| group.sort());
+
+        groups
+    }
+}
+
+fn shots_to_csv<'a>(area: usize, shots: impl Iterator<Item = &'a Shot>) -> String {
+    let mut csv = String::new();
+
+    for shot in shots {
+        csv += &format!(
+            "{},{},{},{},{},{},{}\n",
+            area, shot.vel.0, shot.vel.1, shot.apex, shot.flight, shot.entry.0, shot.entry.1
+        );
+    }
+
+    csv
+}
+
+impl Area {
+    fn contains(&self, pos: (isize, isize)) -> bool {
+        (self.x_min..=self.x_max).contains(&pos.0) && (self.y_min..=self.y_max).contains(&pos.1)
+    }
+
+    fn trajectory(&self, mut vel: (isize, isize)) -> Trajectory {
+        let mut pos = (0, 0);
+        let mut positions = vec![pos];
+
+        loop {
+            pos.0 += vel.0;
+            pos.1 += vel.1;
+            vel.0 -= vel.0.signum();
+            vel.1 -= 1;
+
+            positions.push(pos);
+
+            if self.contains(pos) {
+                return Trajectory {
+                    entry_step: Some(positions.len() - 1),
+                    positions,
+                };
+            }
+
+            let is_past_x = match vel.0.signum() {
+                1 => pos.0 > self.x_max,
+                -1 => pos.0 < self.x_min,
+                _ => pos.0 < self.x_min || pos.0 > self.x_max,
+            };
+
+            if is_past_x || (vel.1 < 0 && pos.1 < self.y_min) {
+                return Trajectory {
+                    positions,
+                    entry_step: None,
+                };
+            }
+        }
+    }
+
+    fn plot(&self, trajectory: &Trajectory) -> String {
+        let xs = trajectory.positions.iter().map(|pos| pos.0);
+        let ys = trajectory.positions.iter().map(|pos| pos.1);
+
+        let x_min = xs.clone().chain([self.x_min]).min().unwrap_or(0);
+        let x_max = xs.chain([self.x_max]).max().unwrap_or(0);
+        let y_min = ys.clone().chain([self.y_min]).min().unwrap_or(0);
+        let y_max = ys.chain([self.y_max]).max().unwrap_or(0);
+
+        let positions = trajectory.positions.iter().collect::<HashSet<_>>();
+
+        (y_min..=y_max)
+            .rev()
+            .map(|y| {
+                (x_min..=x_max)
+                    .map(|x| match (x, y) {
+                        (0, 0) => 'S',
+                        pos if positions.contains(&pos) => '#',
+                        pos if self.contains(pos) => 'T',
+                        _ => '.',
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Area {
+    /// Largest step count at which any probe can still be inside the target. Fails when probes
+    /// that have stopped moving sideways keep passing back through `y = 0` inside the target,
+    /// since every sufficiently high shot then hits too.
+    fn max_steps(&self) -> anyhow::Result<isize> {
+        if !(self.y_min..=self.y_max).contains(&0) {
+            return Ok(2 * cmp::max(self.y_min.abs(), self.y_max.abs()));
+        }
 
-    while !area.x_rng.contains(&pos.0) || !area.y_rng.contains(&pos.1) {
-        if pos.0 > area.x_max
-            || (vel.0 == 0 && (!area.x_rng.contains(&pos.0) || pos.1 < area.y_min))
+        if stopped_x_vels(self.x_min, self.x_max).next().is_some()
+            || stopped_x_vels(-self.x_max, -self.x_min).next().is_some()
         {
-            return false;
+            return Err(anyhow::anyhow!(
+                "Target area contains y=0 above a stopping point, infinitely many shots hit!"
+            ));
         }
 
-        pos.0 += vel.0;
-        pos.1 += vel.1;
+        Ok(cmp::max(self.x_min.abs(), self.x_max.abs()))
+    }
+
+    fn x_vels(&self, steps: isize) -> impl Iterator<Item = isize> {
+        let right = pos_x_vels(self.x_min, self.x_max, steps);
+        let left = pos_x_vels(-self.x_max, -self.x_min, steps)
+            .filter(|&vel| vel > 0)
+            .map(|vel| -vel);
+
+        right.chain(left)
+    }
+
+    fn y_vels(&self, steps: isize) -> ops::RangeInclusive<isize> {
+        let fall = steps * (steps - 1) / 2;
+
+        div_ceil(self.y_min + fall, steps)..=div_floor(self.y_max + fall, steps)
+    }
 
-        if vel.0 > 0 {
-            vel.0 -= 1;
-        } else if vel.0 < 0 {
-            vel.0 += 1;
+    fn velocities(&self) -> anyhow::Result<HashSet<(isize, isize)>> {
+        let mut vels = HashSet::new();
+
+        for steps in 1..=self.max_steps()? {
+            let y_vels = self.y_vels(steps);
+
+            if y_vels.is_empty() {
+                continue;
+            }
+
+            for x_vel in self.x_vels(steps) {
+                vels.extend(y_vels.clone().map(|y_vel| (x_vel, y_vel)));
+            }
         }
 
-        vel.1 -= 1;
+        Ok(vels)
+    }
+
+    fn shots(&self, vels: &HashSet<(isize, isize)>) -> anyhow::Result<Vec<Shot>> {
+        let mut shots = vels
+            .iter()
+            .map(|&vel| {
+                let trajectory = self.trajectory(vel);
+                let flight = trajectory.entry_step.ok_or_else(|| {
+                    anyhow::anyhow!("Velocity {:?} does not reach the target area!", vel)
+                })?;
+
+                Ok(Shot {
+                    vel,
+                    apex: cmp::max(vel.1, 0) * (cmp::max(vel.1, 0) + 1) / 2,
+                    flight,
+                    entry: trajectory.positions[flight],
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        shots.sort();
+
+        Ok(shots)
     }
+}
+
+/// Non-negative x-velocities whose probe is within `min..=max` after `steps` steps. The position
+/// is strictly increasing in the velocity, so the matches are those that have already stopped
+/// (`vel <= steps`) followed by those still moving (`vel > steps`).
+fn pos_x_vels(min: isize, max: isize, steps: isize) -> impl Iterator<Item = isize> {
+    let fall = steps * (steps - 1) / 2;
+
+    let stopped = cmp::max(tri_root_ceil(min), 0)..=cmp::min(tri_root_floor(max), steps);
+    let moving = cmp::max(div_ceil(min + fall, steps), steps + 1)..=div_floor(max + fall, steps);
+
+    stopped.chain(moving)
+}
 
-    true
+fn stopped_x_vels(min: isize, max: isize) -> ops::RangeInclusive<isize> {
+    cmp::max(tri_root_ceil(min), 0)..=tri_root_floor(max)
 }
 
-fn part_one(area: &Area) -> Option<isize> {
-    let y_vel = area.y_min.abs() - 1;
-    let y_max = y_vel * (y_vel + 1) / 2;
+/// Largest `n >= 0` with `n * (n + 1) / 2 <= num`, or `-1` if there is none.
+fn tri_root_floor(num: isize) -> isize {
+    match num {
+        num if num < 0 => -1,
+        num => ((8 * num + 1).isqrt() - 1) / 2,
+    }
+}
 
-    Some(y_max)
+/// Smallest `n >= 0` with `n * (n + 1) / 2 >= num`.
+fn tri_root_ceil(num: isize) -> isize {
+    match num {
+        num if num <= 0 => 0,
+        num => tri_root_floor(num - 1) + 1,
+    }
 }
 
-fn part_two(area: &Area) -> usize {
-    let y_abs_max = cmp::max(area.y_min.abs(), area.y_max.abs());
+fn div_floor(lhs: isize, rhs: isize) -> isize {
+    lhs.div_euclid(rhs)
+}
 
-    (0..=area.x_max)
-        .flat_map(|x_vel| (-y_abs_max..=y_abs_max).map(move |y_vel| (x_vel, y_vel)))
-        .filter(|&vel| trick_shot(vel, area))
-        .collect::<Vec<_>>()
-        .len()
+fn div_ceil(lhs: isize, rhs: isize) -> isize {
+    -(-lhs).div_euclid(rhs)
 }
 
-fn main() -> anyhow::Result<()> {
-    let area = Area::read()?;
+fn parse_vel(vel: &str) -> anyhow::Result<(isize, isize)> {
+    let (x_vel, y_vel) = vel
+        .split_once(',')
+        .ok_or_else(|| anyhow::anyhow!("Expected velocity as '<x>,<y>', got '{}'!", vel))?;
+
+    Ok((x_vel.trim().parse()?, y_vel.trim().parse()?))
+}
 
-    println!(
-        "Part one: {}",
-        part_one(&area).ok_or_else(|| anyhow::anyhow!("No answer found!"))?
-    );
+fn part_one(vels: &HashSet<(isize, isize)>) -> Option<isize> {
+    vels.iter()
+        .map(|&(_, y_vel)| cmp::max(y_vel, 0))
+        .map(|y_vel| y_vel * (y_vel + 1) / 2)
+        .max()
+}
+
+fn part_two(vels: &HashSet<(isize, isize)>) -> usize {
+    vels.len()
+}
+
+fn parse_mode(args: &[String]) -> anyhow::Result<Mode> {
+    match args.first().map(String::as_str) {
+        Some("--plot") => {
+            Ok(Mode::Plot(parse_vel(args.get(1).ok_or_else(|| {
+                anyhow::anyhow!("Missing velocity to plot!")
+            })?)?))
+        }
+        Some("--csv") => Ok(Mode::Csv(
+            args.get(1)
+                .map_or(Ok(ShotOrder::Apex), |order| order.parse())?,
+        )),
+        Some(arg) => Err(anyhow::anyhow!("Unknown argument '{}'!", arg)),
+        None => Ok(Mode::Solve),
+    }
+}
+
+fn solve(idx: usize, area: &Area, mode: Mode) -> anyhow::Result<()> {
+    match mode {
+        Mode::Plot(vel) => {
+            let trajectory = area.trajectory(vel);
 
-    println!("Part two: {}", part_two(&area));
+            println!("{}", area.plot(&trajectory));
+
+            match trajectory.entry_step {
+                Some(step) => println!("Entered target area at step {}", step),
+                None => println!("Missed target area"),
+            }
+        }
+        Mode::Csv(order) => {
+            let shots = area.shots(&area.velocities()?)?;
+
+            print!(
+                "{}",
+                shots_to_csv(idx, order.group(&shots).values().flatten())
+            );
+        }
+        Mode::Solve => {
+            let vels = area.velocities()?;
+
+            println!(
+                "Part one: {}",
+                part_one(&vels).ok_or_else(|| anyhow::anyhow!("No answer found!"))?
+            );
+
+            println!("Part two: {}", part_two(&vels));
+        }
+    }
 
     Ok(())
 }
+
+fn main() -> anyhow::Result<()> {
+    let areas = Area::read_all()?;
+    let mode = parse_mode(&env::args().skip(1).collect::<Vec<_>>())?;
+
+    if let Mode::Csv(_) = mode {
+        println!("{}", CSV_HEADER);
+    }
+
+    if let [area] = areas.as_slice() {
+        return solve(1, area, mode);
+    }
+
+    let mut num_failed = 0;
+
+    for (idx, area) in areas.iter().enumerate() {
+        // Area labels would break the CSV, whose rows carry the area number instead.
+        if !matches!(mode, Mode::Csv(_)) {
+            if idx > 0 {
+                println!();
+            }
+
+            println!("{}", area);
+        }
+
+        if let Err(err) = solve(idx + 1, area, mode) {
+            eprintln!("{}: {:#}", area, err);
+            num_failed += 1;
+        }
+    }
+
+    match num_failed {
+        0 => Ok(()),
+        _ => Err(anyhow::anyhow!(
+            "{} of {} target areas failed!",
+            num_failed,
+            areas.len()
+        )),
+    }
+}
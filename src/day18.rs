@@ -1,32 +1,36 @@
 use core::fmt;
-use std::cell::RefCell;
 use std::collections::VecDeque;
 use std::io::{self, BufRead};
 use std::iter::Sum;
+use std::num::NonZeroUsize;
 use std::ops::Add;
-use std::rc::{Rc, Weak};
 use std::str::FromStr;
+use std::thread;
 
 use anyhow::{anyhow, Error, Result};
 
+/// Snailfish numbers compare structurally: plain numbers order before pairs, and pairs order by
+/// their left element, then their right.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 enum SnailFishElem {
-    Num(Rc<RefCell<usize>>),
-    Pair(Rc<RefCell<SnailFishNode>>),
+    Num(usize),
+    Pair(Box<SnailFishNode>),
 }
 
+#[derive(Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
 struct SnailFishNode {
     left: SnailFishElem,
     right: SnailFishElem,
-    parent: Option<Weak<RefCell<Self>>>,
 }
 
-struct SnailFish(Rc<RefCell<SnailFishNode>>);
+#[derive(Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct SnailFish(SnailFishNode);
 
 impl fmt::Debug for SnailFishElem {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Num(num) => write!(fmt, "{}", num.borrow()),
-            Self::Pair(pair) => write!(fmt, "{:?}", &pair.borrow()),
+            Self::Num(num) => write!(fmt, "{}", num),
+            Self::Pair(pair) => write!(fmt, "{:?}", pair),
         }
     }
 }
@@ -39,29 +43,13 @@ impl fmt::Debug for SnailFishNode {
 
 impl fmt::Debug for SnailFish {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(fmt, "{:?}", &self.0.borrow())
+        write!(fmt, "{:?}", &self.0)
     }
 }
 
 impl Default for SnailFishElem {
     fn default() -> Self {
-        Self::Num(Rc::new(RefCell::new(0)))
-    }
-}
-
-impl Default for SnailFishNode {
-    fn default() -> Self {
-        Self {
-            left: SnailFishElem::default(),
-            right: SnailFishElem::default(),
-            parent: None,
-        }
-    }
-}
-
-impl Default for SnailFish {
-    fn default() -> Self {
-        Self(Rc::default())
+        Self::Num(0)
     }
 }
 
@@ -69,32 +57,7 @@ impl FromStr for SnailFish {
     type Err = Error;
 
     fn from_str(str: &str) -> Result<Self> {
-        Self::from_queue(None, &mut str.chars().collect())
-    }
-}
-
-impl PartialEq for SnailFishElem {
-    fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (Self::Num(lhs), Self::Num(rhs)) => *lhs.borrow() == *rhs.borrow(),
-            (Self::Pair(lhs), Self::Pair(rhs)) => lhs.as_ptr() == rhs.as_ptr(),
-            _ => false,
-        }
-    }
-}
-
-impl PartialEq for SnailFishNode {
-    fn eq(&self, other: &Self) -> bool {
-        let is_same_elems = self.left == other.left && self.right == other.right;
-
-        match (
-            self.parent.as_ref().and_then(|parent| parent.upgrade()),
-            other.parent.as_ref().and_then(|parent| parent.upgrade()),
-        ) {
-            (Some(lhs), Some(rhs)) => lhs.as_ptr() == rhs.as_ptr() && is_same_elems,
-            (None, None) => is_same_elems,
-            _ => false,
-        }
+        Ok(Self(SnailFishNode::from_queue(&mut str.chars().collect())?))
     }
 }
 
@@ -102,62 +65,75 @@ impl Add for SnailFish {
     type Output = Self;
 
     fn add(self, other: Self) -> Self {
-        let fish = Self::default();
-        let parent = fish.to_parent();
-
-        let left = self.with_parent(parent.clone()).into_reduced().unwrap();
-        let right = other.with_parent(parent).into_reduced().unwrap();
-
-        fish.with_left(left)
-            .with_right(right)
-            .into_reduced()
-            .unwrap()
+        self.checked_add(other)
+            .expect("Failed to reduce snailfish sum!")
     }
 }
 
 impl Sum for SnailFish {
     fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
-        let mut iter = iter;
-        let acc = iter.next().unwrap();
-
-        iter.fold(acc, |acc, fish| acc + fish)
+        Self::try_sum(iter).expect("Failed to sum snailfish!")
     }
 }
 
-impl Clone for SnailFish {
-    fn clone(&self) -> Self {
-        Self(Self::clone_node(&self.0))
+impl SnailFishElem {
+    fn explode(&mut self, depth: usize) -> Result<Option<(Option<usize>, Option<usize>)>> {
+        match self {
+            Self::Num(_) => Ok(None),
+            Self::Pair(pair) if depth == 4 => {
+                let (left, right) = match (&pair.left, &pair.right) {
+                    (Self::Num(left), Self::Num(right)) => (*left, *right),
+                    _ => return Err(anyhow!("Exploding pair does not contain two numbers!")),
+                };
+
+                *self = Self::default();
+
+                Ok(Some((Some(left), Some(right))))
+            }
+            Self::Pair(pair) => pair.explode(depth),
+        }
     }
-}
 
-impl SnailFish {
-    fn from_stdin() -> Result<Vec<Self>> {
-        let stdin = io::stdin();
-        let mut lines = stdin.lock().lines();
-        let mut input = vec![];
+    fn split(&mut self) -> bool {
+        match self {
+            Self::Num(num) if *num >= 10 => {
+                *self = Self::Pair(Box::new(SnailFishNode {
+                    left: Self::Num(*num / 2),
+                    right: Self::Num(num.div_ceil(2)),
+                }));
 
-        while let Some(Ok(line)) = lines.next() {
-            if line.is_empty() {
-                break;
+                true
             }
+            Self::Num(_) => false,
+            Self::Pair(pair) => pair.split(),
+        }
+    }
 
-            input.push(line);
+    fn add_leftmost(&mut self, num: usize) {
+        match self {
+            Self::Num(elem) => *elem += num,
+            Self::Pair(pair) => pair.left.add_leftmost(num),
         }
+    }
 
-        input
-            .into_iter()
-            .map(|fish| fish.parse())
-            .collect::<Result<_>>()
+    fn add_rightmost(&mut self, num: usize) {
+        match self {
+            Self::Num(elem) => *elem += num,
+            Self::Pair(pair) => pair.right.add_rightmost(num),
+        }
     }
 
-    fn from_queue(
-        parent: Option<Weak<RefCell<SnailFishNode>>>,
-        input: &mut VecDeque<char>,
-    ) -> Result<Self> {
-        let fish = match parent {
-            Some(parent) => Self::default().with_parent(parent),
-            None => Self::default(),
-        };
+    fn to_magnitude(&self) -> usize {
+        match self {
+            Self::Num(num) => *num,
+            Self::Pair(pair) => pair.to_magnitude(),
+        }
+    }
+}
+
+impl SnailFishNode {
+    fn from_queue(input: &mut VecDeque<char>) -> Result<Self> {
+        let mut node = Self::default();
 
         if input.pop_front().ok_or_else(|| anyhow!("Missing '['!"))? != '[' {
             return Err(anyhow!("Expected '['!"));
@@ -166,13 +142,13 @@ impl SnailFish {
         let mut is_left = true;
 
         while let Some(c) = input.pop_front() {
-            if c.is_digit(10) {
-                fish.parse_num(c, is_left)?;
+            if c.is_ascii_digit() {
+                node.parse_num(c, is_left)?;
             } else if c == ',' {
                 is_left = false;
                 continue;
             } else if c == '[' {
-                fish.parse_pair(input, is_left)?;
+                node.parse_pair(input, is_left)?;
             } else if c == ']' {
                 input.push_front(']');
                 break;
@@ -185,336 +161,189 @@ impl SnailFish {
             return Err(anyhow!("Expected ']'!"));
         }
 
-        Ok(fish)
-    }
-
-    fn with_parent(self, parent: Weak<RefCell<SnailFishNode>>) -> Self {
-        self.0.borrow_mut().parent = Some(parent);
-        Self(self.0)
+        Ok(node)
     }
 
-    fn with_left(self, left: Self) -> Self {
-        self.0.borrow_mut().left = SnailFishElem::Pair(left.0);
-        Self(self.0)
-    }
-
-    fn with_right(self, right: Self) -> Self {
-        self.0.borrow_mut().right = SnailFishElem::Pair(right.0);
-        Self(self.0)
-    }
-
-    fn to_parent(&self) -> Weak<RefCell<SnailFishNode>> {
-        Rc::downgrade(&self.0)
-    }
-
-    fn parse_num(&self, num: char, is_left: bool) -> Result<()> {
-        let num = SnailFishElem::Num(Rc::new(RefCell::new(
+    fn parse_num(&mut self, num: char, is_left: bool) -> Result<()> {
+        let num = SnailFishElem::Num(
             num.to_digit(10)
                 .map(|num| num as usize)
                 .ok_or_else(|| anyhow!("Failed to parse num!"))?,
-        )));
+        );
 
         match is_left {
-            true => self.0.borrow_mut().left = num,
-            false => self.0.borrow_mut().right = num,
+            true => self.left = num,
+            false => self.right = num,
         }
 
         Ok(())
     }
 
-    fn parse_pair(&self, input: &mut VecDeque<char>, is_left: bool) -> Result<()> {
+    fn parse_pair(&mut self, input: &mut VecDeque<char>, is_left: bool) -> Result<()> {
         input.push_front('[');
 
-        let pair = SnailFishElem::Pair(Self::from_queue(Some(self.to_parent()), input)?.0);
+        let pair = SnailFishElem::Pair(Box::new(Self::from_queue(input)?));
 
         match is_left {
-            true => self.0.borrow_mut().left = pair,
-            false => self.0.borrow_mut().right = pair,
+            true => self.left = pair,
+            false => self.right = pair,
         }
 
         Ok(())
     }
 
-    fn into_reduced(self) -> Result<Self> {
-        loop {
-            if let Some(node) = self.find_exploded(&self.0, 0, &mut false)? {
-                self.explode_node(&node)?;
-            } else if let Some((node, is_left)) = Self::find_split(&self.0) {
-                Self::split_node(&node, is_left)?;
-            } else {
-                break;
+    fn explode(&mut self, depth: usize) -> Result<Option<(Option<usize>, Option<usize>)>> {
+        if let Some((left, right)) = self.left.explode(depth + 1)? {
+            if let Some(right) = right {
+                self.right.add_leftmost(right);
             }
-        }
 
-        Ok(self)
-    }
-
-    fn find_exploded(
-        &self,
-        node: &Rc<RefCell<SnailFishNode>>,
-        depth: usize,
-        is_reduced: &mut bool,
-    ) -> Result<Option<Rc<RefCell<SnailFishNode>>>> {
-        if depth == 4 {
-            return Ok(Some(node.clone()));
-        } else if !*is_reduced {
-            if let SnailFishElem::Pair(left) = &node.borrow().left {
-                if let Some(node) = self.find_exploded(left, depth + 1, is_reduced)? {
-                    return Ok(Some(node));
-                }
-            }
-
-            if let SnailFishElem::Pair(right) = &node.borrow().right {
-                if let Some(node) = self.find_exploded(right, depth + 1, is_reduced)? {
-                    return Ok(Some(node));
-                }
-            }
+            return Ok(Some((left, None)));
         }
 
-        Ok(None)
-    }
-
-    fn explode_node(&self, node: &Rc<RefCell<SnailFishNode>>) -> Result<()> {
-        let (left_num, right_num) = match (&node.borrow().left, &node.borrow().right) {
-            (SnailFishElem::Num(left_num), SnailFishElem::Num(right_num)) => {
-                (left_num.borrow().to_owned(), right_num.borrow().to_owned())
+        if let Some((left, right)) = self.right.explode(depth + 1)? {
+            if let Some(left) = left {
+                self.left.add_rightmost(left);
             }
-            _ => return Err(anyhow!("Exploding pair does not contain two numbers!")),
-        };
-
-        if let Some(num) = self.find_closest_num(node, true)? {
-            *num.borrow_mut() += left_num;
-        }
-
-        if let Some(num) = self.find_closest_num(node, false)? {
-            *num.borrow_mut() += right_num;
-        }
-
-        let parent = node
-            .borrow()
-            .parent
-            .as_ref()
-            .and_then(|parent| parent.upgrade())
-            .unwrap();
-
-        let is_left_child = if let SnailFishElem::Pair(left) = &parent.borrow().left {
-            &*left.borrow() == &*node.borrow()
-        } else {
-            false
-        };
-
-        match is_left_child {
-            true => parent.borrow_mut().left = SnailFishElem::default(),
-            false => parent.borrow_mut().right = SnailFishElem::default(),
-        }
 
-        Ok(())
-    }
-
-    fn find_closest_num(
-        &self,
-        node: &Rc<RefCell<SnailFishNode>>,
-        is_left: bool,
-    ) -> Result<Option<Rc<RefCell<usize>>>> {
-        let num = match is_left {
-            true => match &node.borrow().left {
-                SnailFishElem::Num(num) => num.clone(),
-                SnailFishElem::Pair(_) => {
-                    return Err(anyhow!("Exploding pair does not contain a number!"));
-                }
-            },
-            false => match &node.borrow().right {
-                SnailFishElem::Num(num) => num.clone(),
-                SnailFishElem::Pair(_) => {
-                    return Err(anyhow!("Exploding pair does not contain a number!"))
-                }
-            },
-        };
-
-        let flat = self.to_sorted_vec();
-        let idx = flat
-            .iter()
-            .enumerate()
-            .find(|&(_, flat_num)| flat_num.as_ptr() == num.as_ptr())
-            .map(|(idx, _)| idx)
-            .unwrap();
-
-        if idx == 0 || idx == flat.len() - 1 {
-            return Ok(None);
+            return Ok(Some((None, right)));
         }
 
-        let num = match is_left {
-            true => &flat[idx - 1],
-            false => &flat[idx + 1],
-        };
-
-        Ok(Some(num.clone()))
+        Ok(None)
     }
 
-    fn to_sorted_vec(&self) -> Vec<Rc<RefCell<usize>>> {
-        let mut vec = vec![];
-        Self::in_order_nums(&self.0, &mut vec);
-
-        vec
+    fn split(&mut self) -> bool {
+        self.left.split() || self.right.split()
     }
 
     fn to_magnitude(&self) -> usize {
-        let mut magnitude = 0;
-        Self::in_order_magnitude(&self.0, 1, &mut magnitude);
-
-        magnitude
+        3 * self.left.to_magnitude() + 2 * self.right.to_magnitude()
     }
+}
 
-    fn find_split(node: &Rc<RefCell<SnailFishNode>>) -> Option<(Rc<RefCell<SnailFishNode>>, bool)> {
-        if let SnailFishElem::Pair(node) = &node.borrow().left {
-            if let Some((node, is_left)) = Self::find_split(node) {
-                return Some((node, is_left));
-            }
-        }
-
-        if let SnailFishElem::Num(num) = &node.borrow().left {
-            if *num.borrow() >= 10 {
-                return Some((node.clone(), true));
-            }
-        }
+impl SnailFish {
+    fn from_stdin() -> Result<Vec<Self>> {
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+        let mut input = vec![];
 
-        if let SnailFishElem::Num(num) = &node.borrow().right {
-            if *num.borrow() >= 10 {
-                return Some((node.clone(), false));
+        while let Some(Ok(line)) = lines.next() {
+            if line.is_empty() {
+                break;
             }
-        }
 
-        if let SnailFishElem::Pair(node) = &node.borrow().right {
-            if let Some((node, is_left)) = Self::find_split(node) {
-                return Some((node, is_left));
-            }
+            input.push(line);
         }
 
-        None
+        input
+            .into_iter()
+            .map(|fish| fish.parse())
+            .collect::<Result<_>>()
     }
 
-    fn split_node(node: &Rc<RefCell<SnailFishNode>>, is_left: bool) -> Result<()> {
-        let num = match is_left {
-            true => match &node.borrow().left {
-                SnailFishElem::Num(num) => *num.borrow(),
-                SnailFishElem::Pair(_) => {
-                    return Err(anyhow!("Split pair does not contain a number!"))
-                }
-            },
-            false => match &node.borrow().right {
-                SnailFishElem::Num(num) => *num.borrow(),
-                SnailFishElem::Pair(_) => {
-                    return Err(anyhow!("Split pair does not contain a number!"))
-                }
-            },
-        };
-
-        let fish = Self::default().with_parent(Rc::downgrade(node));
-        let num = num as f32 / 2.0;
-
-        fish.0.borrow_mut().left = SnailFishElem::Num(Rc::new(RefCell::new(num.floor() as usize)));
-        fish.0.borrow_mut().right = SnailFishElem::Num(Rc::new(RefCell::new(num.ceil() as usize)));
+    fn checked_add(self, other: Self) -> Result<Self> {
+        let left = self.into_reduced()?;
+        let right = other.into_reduced()?;
 
-        match is_left {
-            true => node.borrow_mut().left = SnailFishElem::Pair(fish.0),
-            false => node.borrow_mut().right = SnailFishElem::Pair(fish.0),
-        };
-
-        Ok(())
+        Self(SnailFishNode {
+            left: SnailFishElem::Pair(Box::new(left.0)),
+            right: SnailFishElem::Pair(Box::new(right.0)),
+        })
+        .into_reduced()
     }
 
-    fn in_order_nums(node: &Rc<RefCell<SnailFishNode>>, nums: &mut Vec<Rc<RefCell<usize>>>) -> () {
-        if let SnailFishElem::Pair(left) = &node.borrow().left {
-            Self::in_order_nums(left, nums);
-        }
-
-        if let SnailFishElem::Num(num) = &node.borrow().left {
-            nums.push(num.clone());
-        }
+    fn try_sum<I: IntoIterator<Item = Self>>(iter: I) -> Result<Self> {
+        let mut iter = iter.into_iter();
+        let acc = iter
+            .next()
+            .ok_or_else(|| anyhow!("Cannot sum an empty list of snailfish!"))?;
 
-        if let SnailFishElem::Num(num) = &node.borrow().right {
-            nums.push(num.clone());
-        }
-
-        if let SnailFishElem::Pair(right) = &node.borrow().right {
-            Self::in_order_nums(right, nums);
-        }
+        iter.try_fold(acc, Self::checked_add)
     }
 
-    fn in_order_magnitude(
-        node: &Rc<RefCell<SnailFishNode>>,
-        multi: usize,
-        magnitude: &mut usize,
-    ) -> () {
-        if let SnailFishElem::Pair(left) = &node.borrow().left {
-            Self::in_order_magnitude(left, multi * 3, magnitude);
-        }
-
-        if let SnailFishElem::Num(num) = &node.borrow().left {
-            *magnitude += 3 * multi * *num.borrow();
-        }
+    fn into_reduced(mut self) -> Result<Self> {
+        while self.0.explode(0)?.is_some() || self.0.split() {}
 
-        if let SnailFishElem::Num(num) = &node.borrow().right {
-            *magnitude += 2 * multi * *num.borrow();
-        }
+        Ok(self)
+    }
 
-        if let SnailFishElem::Pair(right) = &node.borrow().right {
-            Self::in_order_magnitude(right, multi * 2, magnitude);
-        }
+    fn to_magnitude(&self) -> usize {
+        self.0.to_magnitude()
     }
 
-    fn clone_node(node: &Rc<RefCell<SnailFishNode>>) -> Rc<RefCell<SnailFishNode>> {
-        let new_node = Rc::new(RefCell::new(SnailFishNode::default()));
+    /// Finds the largest magnitude of `fish[i] + fish[j]` over every `i` in `rows` and every
+    /// `j != i`, returning it along with the `(i, j)` that produced it.
+    fn largest_sum(
+        fish: &[Self],
+        rows: impl Iterator<Item = usize>,
+    ) -> Result<Option<(usize, (usize, usize))>> {
+        let mut largest: Option<(usize, (usize, usize))> = None;
 
-        match &node.borrow().left {
-            SnailFishElem::Num(num) => {
-                new_node.borrow_mut().left =
-                    SnailFishElem::Num(Rc::new(RefCell::new(*num.borrow())))
-            }
-            SnailFishElem::Pair(left) => {
-                let new_left = Self::clone_node(left);
-                new_left.borrow_mut().parent = Some(Rc::downgrade(&new_node));
-                new_node.borrow_mut().left = SnailFishElem::Pair(new_left)
-            }
-        }
+        for i in rows {
+            for j in (0..fish.len()).filter(|&j| j != i) {
+                let magnitude = fish[i].clone().checked_add(fish[j].clone())?.to_magnitude();
 
-        match &node.borrow().right {
-            SnailFishElem::Num(num) => {
-                new_node.borrow_mut().right =
-                    SnailFishElem::Num(Rc::new(RefCell::new(*num.borrow())))
-            }
-            SnailFishElem::Pair(right) => {
-                let new_right = Self::clone_node(right);
-                new_right.borrow_mut().parent = Some(Rc::downgrade(&new_node));
-                new_node.borrow_mut().right = SnailFishElem::Pair(new_right)
+                if largest.is_none_or(|(largest, _)| magnitude > largest) {
+                    largest = Some((magnitude, (i, j)));
+                }
             }
         }
 
-        new_node
+        Ok(largest)
     }
 }
 
-fn part_one(fish: Vec<SnailFish>) -> usize {
-    fish.into_iter().sum::<SnailFish>().to_magnitude()
+fn part_one(fish: Vec<SnailFish>) -> Result<usize> {
+    Ok(SnailFish::try_sum(fish)?.to_magnitude())
 }
 
-fn part_two(fish: &[SnailFish]) -> usize {
-    fish.iter()
-        .flat_map(|i| fish.iter().map(move |j| (i, j)))
-        .map(|(i, j)| (i.clone(), j.clone()))
-        .map(|(i, j)| i + j)
-        .map(|fish| fish.to_magnitude())
-        .max()
-        .unwrap()
+fn part_two(fish: &[SnailFish]) -> Result<(usize, (usize, usize))> {
+    let threads = thread::available_parallelism()
+        .map(NonZeroUsize::get)
+        .unwrap_or(1);
+    let chunk_len = fish.len().div_ceil(threads).max(1);
+
+    let largest = thread::scope(|scope| {
+        let handles = (0..fish.len())
+            .step_by(chunk_len)
+            .map(|start| {
+                let rows = start..fish.len().min(start + chunk_len);
+                scope.spawn(move || SnailFish::largest_sum(fish, rows))
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .map_err(|_| anyhow!("Snailfish search thread panicked!"))?
+            })
+            .collect::<Result<Vec<_>>>()
+    })?;
+
+    // Chunks are in row order, so keeping the first maximum makes ties resolve to the
+    // lowest pair of lines regardless of how the rows were split across threads.
+    largest
+        .into_iter()
+        .flatten()
+        .fold(
+            None,
+            |acc: Option<(usize, (usize, usize))>, largest| match acc {
+                Some(acc) if acc.0 >= largest.0 => Some(acc),
+                _ => Some(largest),
+            },
+        )
+        .ok_or_else(|| anyhow!("Need at least two snailfish to find the largest sum!"))
 }
 
 fn main() -> Result<()> {
     let fish = SnailFish::from_stdin()?;
 
-    let part_two = part_two(&fish);
+    println!("Part one: {}", part_one(fish.clone())?);
 
-    println!("Part one: {}", part_one(fish));
-    println!("Part two: {}", part_two);
+    let (part_two, (i, j)) = part_two(&fish)?;
+    println!("Part two: {} (lines {} + {})", part_two, i + 1, j + 1);
 
     Ok(())
 }
@@ -1,116 +1,69 @@
-use std::str;
+use std::env;
+use std::io;
 
 extern crate anyhow;
 
-use aoc2021_rust::util;
+use aoc2021_rust::submarine::{self, Aimed, Command, Direct, Interpreter, MovementModel};
 
-const FORWARD: &str = "forward";
-const UP: &str = "up";
-const DOWN: &str = "down";
-
-#[derive(Debug)]
-enum Direction {
-    Forward,
-    Up,
-    Down,
-}
-
-#[derive(Debug)]
-struct Command {
-    direction: Direction,
-    units: isize,
+fn part_one(commands: &[Command]) -> anyhow::Result<isize> {
+    Ok(Interpreter::new(Direct).run(commands).result())
 }
 
-#[derive(Debug)]
-struct Position {
-    pos: isize,
-    depth: isize,
-    aim: isize,
+fn part_two(commands: &[Command]) -> anyhow::Result<isize> {
+    Ok(Interpreter::new(Aimed).run(commands).result())
 }
 
-impl str::FromStr for Direction {
-    type Err = anyhow::Error;
-
-    fn from_str(input: &str) -> anyhow::Result<Self> {
-        match input.to_lowercase().as_str() {
-            FORWARD => Ok(Self::Forward),
-            UP => Ok(Self::Up),
-            DOWN => Ok(Self::Down),
-            _ => Err(anyhow::anyhow!("Unknown direction '{}'!", input)),
+/// Prints the trajectory as CSV on stdout and its summary on stderr, keeping the CSV clean.
+fn print_log(model: impl MovementModel, commands: &[Command]) -> anyhow::Result<()> {
+    let mut interpreter = Interpreter::new(model).with_log();
+    interpreter.run(commands);
+
+    let log = interpreter
+        .log()
+        .ok_or_else(|| anyhow::anyhow!("Submarine trajectory was not recorded!"))?;
+
+    print!("{}", submarine::log_to_csv(log));
+
+    eprintln!(
+        "Max depth: {}",
+        submarine::max_depth(log).ok_or_else(|| anyhow::anyhow!("Empty trajectory!"))?
+    );
+    eprintln!(
+        "Went above surface: {}",
+        match submarine::went_above_surface(log) {
+            true => "yes",
+            false => "no",
         }
-    }
-}
+    );
 
-impl str::FromStr for Command {
-    type Err = anyhow::Error;
-
-    fn from_str(input: &str) -> anyhow::Result<Self> {
-        let split = input.split(' ').collect::<Vec<_>>();
-        Ok(Command {
-            direction: split
-                .first()
-                .map(|&direction| direction.parse::<Direction>())
-                .ok_or_else(|| anyhow::anyhow!("Missing command!"))??,
-            units: split
-                .last()
-                .map(|&units| units.parse())
-                .ok_or_else(|| anyhow::anyhow!("Missing units!"))??,
-        })
-    }
+    Ok(())
 }
 
-impl Position {
-    fn new() -> Self {
-        Self {
-            pos: 0,
-            depth: 0,
-            aim: 0,
-        }
-    }
-
-    fn update(&mut self, command: &Command) -> () {
-        match command.direction {
-            Direction::Forward => self.pos += command.units,
-            Direction::Up => self.depth -= command.units,
-            Direction::Down => self.depth += command.units,
-        }
-    }
-
-    fn update_with_aim(&mut self, command: &Command) -> () {
-        match command.direction {
-            Direction::Forward => {
-                self.pos += command.units;
-                self.depth += self.aim * command.units;
-            }
-            Direction::Up => self.aim -= command.units,
-            Direction::Down => self.aim += command.units,
+fn main() -> anyhow::Result<()> {
+    let mut args = env::args().skip(1);
+    let log = match args.next().as_deref() {
+        Some("--log") => Some(
+            args.next()
+                .ok_or_else(|| anyhow::anyhow!("Missing movement model to log!"))?,
+        ),
+        Some(arg) => return Err(anyhow::anyhow!("Unknown argument '{}'!", arg)),
+        None => None,
+    };
+
+    let input = submarine::parse_script(&io::read_to_string(io::stdin())?)?;
+
+    match log.as_deref() {
+        Some("direct") => return print_log(Direct, &input),
+        Some("aimed") => return print_log(Aimed, &input),
+        Some(model) => {
+            return Err(anyhow::anyhow!(
+                "Unknown movement model '{}', expected 'direct' or 'aimed'!",
+                model
+            ))
         }
+        None => (),
     }
 
-    fn result(&self) -> isize {
-        self.pos * self.depth
-    }
-}
-
-fn part_one(commands: &[Command]) -> anyhow::Result<isize> {
-    let mut pos = Position::new();
-    commands.iter().for_each(|command| pos.update(command));
-
-    Ok(pos.result())
-}
-
-fn part_two(commands: &[Command]) -> anyhow::Result<isize> {
-    let mut pos = Position::new();
-    commands
-        .iter()
-        .for_each(|command| pos.update_with_aim(command));
-
-    Ok(pos.result())
-}
-
-fn main() -> anyhow::Result<()> {
-    let input = util::read_input::<Command>()?;
-
     println!("Part one: {}", part_one(&input)?);
     println!("Part two: {}", part_two(&input)?);
 
@@ -1,27 +1,31 @@
+use std::env;
 use std::str;
 
 extern crate anyhow;
 
 use aoc2021_rust::util;
 
-#[derive(Debug, Clone)]
-struct BinNo(Vec<u8>);
-
-impl BinNo {
-    fn into_decimal(self) -> anyhow::Result<usize> {
-        usize::from_str_radix(
-            self.0
-                .into_iter()
-                .map(|bit| {
-                    char::from_digit(bit as u32, 2)
-                        .ok_or_else(|| anyhow::anyhow!("Invalid binary digit!"))
-                })
-                .collect::<Result<String, _>>()?
-                .as_str(),
-            2,
-        )
-        .map_err(|err| anyhow::anyhow!("{}", err))
-    }
+const MAX_WIDTH: usize = u64::BITS as usize;
+
+#[derive(Debug, Clone, Copy)]
+struct BinNo {
+    bits: u64,
+    width: usize,
+}
+
+/// Bit that counts as the most common in a column with as many zeros as ones. The least common
+/// bit on a tie is then the other one.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TieBreak {
+    Ones,
+    Zeros,
+}
+
+/// Diagnostic report numbers packed into `u64`s and kept sorted, so numbers sharing their
+/// leading bits form a contiguous range.
+struct DiagnosticReport {
+    nums: Vec<u64>,
+    width: usize,
 }
 
 impl str::FromStr for BinNo {
@@ -32,113 +36,171 @@ impl str::FromStr for BinNo {
             return Err(anyhow::anyhow!("Unexpected empty binary number input!"));
         }
 
-        Ok(BinNo(
-            input
-                .chars()
-                .map(|c| c.to_digit(2))
-                .map(|digit| digit.map(|digit| digit as u8))
-                .map(|digit| digit.ok_or_else(|| anyhow::anyhow!("Invalid binary digit!")))
-                .collect::<Result<Vec<_>, _>>()?,
-        ))
+        if input.len() > MAX_WIDTH {
+            return Err(anyhow::anyhow!(
+                "Binary number '{}' is wider than {} bits!",
+                input,
+                MAX_WIDTH
+            ));
+        }
+
+        let bits = input.chars().try_fold(0, |bits, c| match c {
+            '0' => Ok(bits << 1),
+            '1' => Ok(bits << 1 | 1),
+            _ => Err(anyhow::anyhow!(
+                "Invalid binary digit '{}' in '{}'!",
+                c,
+                input
+            )),
+        })?;
+
+        Ok(BinNo {
+            bits,
+            width: input.len(),
+        })
     }
 }
 
-fn part_one(diagnostic_report: &[BinNo]) -> anyhow::Result<usize> {
-    let bin_no_len = diagnostic_report
-        .first()
-        .map(|report| report.0.len())
-        .ok_or_else(|| anyhow::anyhow!("Unexpected empty diagnostic report!"))?;
-
-    let report_len = diagnostic_report.len();
-
-    let mut gamma_rate = BinNo(vec![0; bin_no_len]);
-    let mut epsilon_rate = BinNo(vec![0; bin_no_len]);
-
-    (0..bin_no_len).for_each(|i| {
-        let num_zeros = diagnostic_report
-            .iter()
-            .map(|report| report.0[i])
-            .filter(|&bit| bit == 0)
-            .collect::<Vec<_>>()
-            .len();
-
-        if num_zeros > report_len - num_zeros {
-            gamma_rate.0[i] = 0;
-            epsilon_rate.0[i] = 1;
-        } else {
-            gamma_rate.0[i] = 1;
-            epsilon_rate.0[i] = 0;
-        }
-    });
+impl str::FromStr for TieBreak {
+    type Err = anyhow::Error;
 
-    Ok(gamma_rate.into_decimal()? * epsilon_rate.into_decimal()?)
+    fn from_str(tie_break: &str) -> anyhow::Result<Self> {
+        match tie_break {
+            "ones" => Ok(Self::Ones),
+            "zeros" => Ok(Self::Zeros),
+            _ => Err(anyhow::anyhow!(
+                "Unknown tie-break '{}', expected 'ones' or 'zeros'!",
+                tie_break
+            )),
+        }
+    }
 }
 
-fn part_two(diagnostic_report: &[BinNo]) -> anyhow::Result<usize> {
-    let bin_no_len = diagnostic_report
-        .first()
-        .map(|report| report.0.len())
-        .ok_or_else(|| anyhow::anyhow!("Unexpected empty diagnostic report!"))?;
-
-    let mut o2_rating = diagnostic_report.iter().cloned().collect::<Vec<_>>();
-    let mut co2_rating = diagnostic_report.iter().cloned().collect::<Vec<_>>();
-
-    (0..bin_no_len).for_each(|i| {
-        filter_rating(&mut o2_rating, i, true);
-        filter_rating(&mut co2_rating, i, false);
-    });
-
-    Ok(o2_rating
-        .into_iter()
-        .next()
-        .ok_or_else(|| anyhow::anyhow!("No answer found!"))?
-        .into_decimal()?
-        * co2_rating
-            .into_iter()
-            .next()
-            .ok_or_else(|| anyhow::anyhow!("No answer found!"))?
-            .into_decimal()?)
+impl TieBreak {
+    fn is_one_most_common(self, num_zeros: usize, num_ones: usize) -> bool {
+        match num_zeros == num_ones {
+            true => self == Self::Ones,
+            false => num_ones > num_zeros,
+        }
+    }
 }
 
-fn filter_rating(report: &mut Vec<BinNo>, bit_idx: usize, most_common_bit: bool) -> () {
-    let report_len = report.len();
+impl DiagnosticReport {
+    fn new(nums: &[BinNo]) -> anyhow::Result<Self> {
+        let width = nums
+            .first()
+            .map(|num| num.width)
+            .ok_or_else(|| anyhow::anyhow!("Unexpected empty diagnostic report!"))?;
+
+        if let Some(num) = nums.iter().find(|num| num.width != width) {
+            return Err(anyhow::anyhow!(
+                "Binary number '{:0width$b}' has {} bits, expected {}!",
+                num.bits,
+                num.width,
+                width,
+                width = num.width
+            ));
+        }
+
+        let mut nums = nums.iter().map(|num| num.bits).collect::<Vec<_>>();
+        nums.sort_unstable();
+
+        Ok(Self { nums, width })
+    }
+
+    /// Number of ones in each column, indexed by bit position from the least significant bit.
+    fn column_popcounts(&self) -> Vec<usize> {
+        let mut popcounts = vec![0; self.width];
+
+        for &num in self.nums.iter() {
+            let mut bits = num;
 
-    if report_len == 1 {
-        return;
+            while bits != 0 {
+                popcounts[bits.trailing_zeros() as usize] += 1;
+                bits &= bits - 1;
+            }
+        }
+
+        popcounts
+    }
+
+    fn power_rates(&self, tie_break: TieBreak) -> (u64, u64) {
+        let mask = u64::MAX >> (MAX_WIDTH - self.width);
+        let gamma_rate = self
+            .column_popcounts()
+            .into_iter()
+            .enumerate()
+            .filter(|&(_, num_ones)| {
+                tie_break.is_one_most_common(self.nums.len() - num_ones, num_ones)
+            })
+            .fold(0, |rate, (bit, _)| rate | 1 << bit);
+
+        (gamma_rate, !gamma_rate & mask)
     }
 
-    let num_zeros = report
-        .iter()
-        .map(|report| report.0[bit_idx])
-        .filter(|&bit| bit == 0)
-        .collect::<Vec<_>>()
-        .len();
-
-    let bit = if num_zeros > report_len - num_zeros {
-        if most_common_bit {
-            0
-        } else {
-            1
+    /// Narrows down the numbers bit by bit from the most significant, keeping those with the
+    /// most (or least) common bit in that position.
+    fn rating(&self, most_common_bit: bool, tie_break: TieBreak) -> anyhow::Result<u64> {
+        let (mut lo, mut hi) = (0, self.nums.len());
+
+        for bit in (0..self.width).rev() {
+            if hi - lo <= 1 {
+                break;
+            }
+
+            let split = lo + self.nums[lo..hi].partition_point(|&num| num >> bit & 1 == 0);
+            let (num_zeros, num_ones) = (split - lo, hi - split);
+
+            match tie_break.is_one_most_common(num_zeros, num_ones) == most_common_bit {
+                true => lo = split,
+                false => hi = split,
+            }
         }
-    } else {
-        if most_common_bit {
-            1
-        } else {
-            0
+
+        // Any numbers left once every bit is used are duplicates of each other.
+        match lo < hi {
+            true => Ok(self.nums[lo]),
+            false => Err(anyhow::anyhow!("No answer found!")),
         }
-    };
+    }
+}
+
+fn part_one(report: &DiagnosticReport, tie_break: TieBreak) -> anyhow::Result<u128> {
+    let (gamma_rate, epsilon_rate) = report.power_rates(tie_break);
+
+    Ok(gamma_rate as u128 * epsilon_rate as u128)
+}
+
+fn part_two(report: &DiagnosticReport, tie_break: TieBreak) -> anyhow::Result<u128> {
+    let o2_rating = report.rating(true, tie_break)?;
+    let co2_rating = report.rating(false, tie_break)?;
 
-    *report = report
-        .drain(..)
-        .filter(|report| report.0[bit_idx] == bit)
-        .collect::<Vec<_>>()
+    Ok(o2_rating as u128 * co2_rating as u128)
 }
 
 fn main() -> anyhow::Result<()> {
-    let input = util::read_input::<BinNo>()?;
+    let mut args = env::args().skip(1);
+    let mut power_tie_break = TieBreak::Ones;
+    let mut rating_tie_break = TieBreak::Ones;
+
+    while let Some(arg) = args.next() {
+        let tie_break = args
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Missing value for argument '{}'!", arg))?
+            .parse::<TieBreak>()?;
+
+        match arg.as_str() {
+            "--power-ties" => power_tie_break = tie_break,
+            "--rating-ties" => rating_tie_break = tie_break,
+            _ => return Err(anyhow::anyhow!("Unknown argument '{}'!", arg)),
+        }
+    }
+
+    let input = util::read_lines::<BinNo>()?;
+    let report = DiagnosticReport::new(&input)?;
 
-    println!("Part one: {}", part_one(&input)?);
-    println!("Part two: {}", part_two(&input)?);
+    println!("Part one: {}", part_one(&report, power_tie_break)?);
+    println!("Part two: {}", part_two(&report, rating_tie_break)?);
 
     Ok(())
 }
@@ -1,169 +1,131 @@
-use std::collections;
+use std::env;
 use std::io;
-use std::io::BufRead;
 
 extern crate anyhow;
 
-const BOARD_LEN: u8 = 5;
+use aoc2021_rust::bingo::{Bingo, Diagonals, FullCard, Lines, Win, WinCondition};
 
-#[derive(Debug, Clone)]
-struct Cell {
-    num: u8,
-    is_marked: bool,
-}
+/// Replays the draws up to `win`, printing its board after each one and the score breakdown.
+fn replay(bingo: &Bingo, win: &Win) -> anyhow::Result<()> {
+    let mut board = bingo
+        .boards()
+        .get(win.board)
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("Unknown board {}!", win.board + 1))?;
 
-#[derive(Debug, Clone)]
-struct Board {
-    grid: Vec<Cell>,
-    num_idxs: collections::HashMap<u8, u8>,
-}
+    for (draw, &num) in bingo.nums().iter().enumerate().take(win.draw + 1) {
+        let is_on_board = board.mark(num).is_some();
 
-impl Board {
-    fn get(&self, i: u8, j: u8) -> &Cell {
-        &self.grid[((i * BOARD_LEN) + j) as usize]
-    }
+        println!(
+            "Draw {}: {}{}",
+            draw + 1,
+            num,
+            match is_on_board {
+                true => "",
+                false => " (not on board)",
+            }
+        );
 
-    fn get_score(&self, winning_num: u8) -> u32 {
-        self.grid
-            .iter()
-            .filter(|cell| !cell.is_marked)
-            .map(|cell| cell.num as u32)
-            .sum::<u32>()
-            * winning_num as u32
-    }
+        let highlight = match draw == win.draw {
+            true => win.cells.as_slice(),
+            false => &[],
+        };
 
-    fn is_winner(&self) -> bool {
-        (0..BOARD_LEN).any(|i| (0..BOARD_LEN).all(|j| self.get(i, j).is_marked))
-            || (0..BOARD_LEN).any(|j| (0..BOARD_LEN).all(|i| self.get(i, j).is_marked))
+        println!("{}\n", board.render(highlight));
     }
 
-    fn update(self, num: u8) -> Self {
-        let mut grid = self.grid;
-
-        if let Some(&idx) = self.num_idxs.get(&num) {
-            grid[idx as usize].is_marked = true;
-        }
+    println!(
+        "Board {} wins: unmarked sum {} x winning number {} = {}",
+        win.board + 1,
+        board.unmarked_sum(),
+        win.num,
+        win.score
+    );
 
-        Board {
-            grid,
-            num_idxs: self.num_idxs,
-        }
-    }
+    Ok(())
 }
 
-fn read_nums() -> anyhow::Result<Vec<u8>> {
-    io::stdin()
-        .lock()
-        .lines()
-        .next()
-        .ok_or_else(|| anyhow::anyhow!("Empty drawn numbers input!"))??
-        .split(',')
-        .into_iter()
-        .map(|num| num.parse())
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|_| anyhow::anyhow!("Parse error!"))
+fn part_one(wins: &[Win]) -> anyhow::Result<u64> {
+    wins.first()
+        .map(|win| win.score)
+        .ok_or_else(|| anyhow::anyhow!("No winner!"))
 }
 
-fn read_board() -> anyhow::Result<Board> {
-    let grid = io::stdin()
-        .lock()
-        .lines()
-        .skip(1)
-        .take(BOARD_LEN as usize)
-        .into_iter()
-        .map(|line| {
-            line.map(|line| {
-                if line.is_empty() {
-                    return Err(anyhow::anyhow!("Unexpected empty board input line!"));
-                }
-
-                Ok(line
-                    .split_whitespace()
-                    .map(|num| u8::from_str_radix(num, 10))
-                    .collect::<Result<Vec<_>, _>>())
-            })
-        })
-        .collect::<Result<Result<Result<Vec<_>, _>, _>, _>>()???
-        .into_iter()
-        .flatten()
-        .map(|num| Cell {
-            num,
-            is_marked: false,
-        })
-        .collect::<Vec<_>>();
-
-    let num_idxs = grid
-        .iter()
-        .enumerate()
-        .map(|(i, cell)| (cell.num, i as u8))
-        .collect::<collections::HashMap<_, _>>();
-
-    Ok(Board { grid, num_idxs })
+fn part_two(wins: &[Win]) -> anyhow::Result<u64> {
+    wins.last()
+        .map(|win| win.score)
+        .ok_or_else(|| anyhow::anyhow!("No winner!"))
 }
 
-fn read_boards() -> anyhow::Result<Vec<Board>> {
-    let mut boards = vec![];
-    while let Ok(board) = read_board() {
-        boards.push(board);
-    }
-
-    Ok(boards)
-}
-
-fn part_one(nums: &[u8], mut boards: Vec<Board>) -> anyhow::Result<u32> {
-    let mut nums = nums.iter();
-
-    loop {
-        match nums.next() {
-            Some(&num) => {
-                boards = boards
-                    .into_iter()
-                    .map(|board| board.update(num))
-                    .collect::<Vec<_>>();
-
-                if let Some(winner) = boards.iter().find(|&board| board.is_winner()) {
-                    return Ok(winner.get_score(num));
+fn main() -> anyhow::Result<()> {
+    let mut args = env::args().skip(1);
+    let mut win_condition: Box<dyn WinCondition> = Box::new(Lines);
+    let mut show_order = false;
+    let mut replay_last = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--win" => {
+                win_condition = match args.next().as_deref() {
+                    Some("lines") => Box::new(Lines),
+                    Some("diagonals") => Box::new(Diagonals),
+                    Some("full") => Box::new(FullCard),
+                    Some(cond) => {
+                        return Err(anyhow::anyhow!(
+                            "Unknown win condition '{}', expected 'lines', 'diagonals' or 'full'!",
+                            cond
+                        ))
+                    }
+                    None => return Err(anyhow::anyhow!("Missing win condition!")),
                 }
             }
-            None => return Err(anyhow::anyhow!("No winner!")),
+            "--order" => show_order = true,
+            "--replay" => {
+                replay_last = match args.next().as_deref() {
+                    Some("first") => Some(false),
+                    Some("last") => Some(true),
+                    Some(which) => {
+                        return Err(anyhow::anyhow!(
+                            "Unknown replay '{}', expected 'first' or 'last'!",
+                            which
+                        ))
+                    }
+                    None => return Err(anyhow::anyhow!("Missing board to replay!")),
+                }
+            }
+            _ => return Err(anyhow::anyhow!("Unknown argument '{}'!", arg)),
         }
     }
-}
-
-fn part_two(nums: &[u8], mut boards: Vec<Board>) -> anyhow::Result<u32> {
-    let mut nums = nums.iter();
 
-    loop {
-        match nums.next() {
-            Some(&num) => {
-                boards = boards
-                    .into_iter()
-                    .map(|board| board.update(num))
-                    .collect::<Vec<_>>();
+    let bingo = Bingo::read(io::stdin().lock())?;
+    let wins = bingo.play(win_condition.as_ref());
 
-                let last_winner = boards
-                    .iter()
-                    .cloned()
-                    .filter(|board| board.is_winner())
-                    .last();
+    if let Some(replay_last) = replay_last {
+        let win = match replay_last {
+            true => wins.last(),
+            false => wins.first(),
+        }
+        .ok_or_else(|| anyhow::anyhow!("No winner!"))?;
 
-                boards.retain(|board| !board.is_winner());
+        return replay(&bingo, win);
+    }
 
-                if boards.is_empty() && last_winner.is_some() {
-                    return Ok(last_winner.unwrap().get_score(num));
-                }
-            }
-            None => return Err(anyhow::anyhow!("No winner!")),
+    if show_order {
+        for win in wins.iter() {
+            println!(
+                "Board {} wins on draw {} ({}) with score {}",
+                win.board + 1,
+                win.draw + 1,
+                win.num,
+                win.score
+            );
         }
-    }
-}
 
-fn main() -> anyhow::Result<()> {
-    let nums = read_nums()?;
-    let boards = read_boards()?;
+        return Ok(());
+    }
 
-    println!("Part one {}", part_one(&nums, boards.clone())?);
-    println!("Part two {}", part_two(&nums, boards)?);
+    println!("Part one {}", part_one(&wins)?);
+    println!("Part two {}", part_two(&wins)?);
 
     Ok(())
 }
@@ -1,5 +1,5 @@
 use std::cmp;
-use std::collections;
+use std::env;
 use std::fmt;
 use std::str;
 
@@ -16,14 +16,16 @@ impl str::FromStr for Line {
     type Err = anyhow::Error;
 
     fn from_str(input: &str) -> anyhow::Result<Self> {
-        let dirs = input
-            .split(" -> ")
-            .flat_map(|pair| pair.split(',').map(|dir| dir.parse()))
-            .collect::<Result<Vec<_>, _>>()?;
+        let (start, end) = input.trim().split_once("->").ok_or_else(|| {
+            anyhow::anyhow!("Expected a line like 'x1,y1 -> x2,y2', got '{}'!", input)
+        })?;
+
+        let (x0, y0) = Self::parse_point(start)?;
+        let (x1, y1) = Self::parse_point(end)?;
 
         Ok(Line {
-            x: (dirs[0], dirs[2]),
-            y: (dirs[1], dirs[3]),
+            x: (x0, x1),
+            y: (y0, y1),
         })
     }
 }
@@ -45,74 +47,182 @@ impl cmp::PartialEq for Line {
 }
 
 impl Line {
-    fn to_coords(&self, parse_diagonal: bool) -> Option<Vec<(isize, isize)>> {
-        if self.x.0 == self.x.1 {
-            Some(
-                (cmp::min(self.y.0, self.y.1)..=cmp::max(self.y.0, self.y.1))
-                    .map(|y| (self.x.0, y))
-                    .collect(),
-            )
-        } else if self.y.0 == self.y.1 {
-            Some(
-                (cmp::min(self.x.0, self.x.1)..=cmp::max(self.x.0, self.x.1))
-                    .map(|x| (x, self.y.0))
-                    .collect(),
-            )
-        } else if parse_diagonal && ((self.y.1 - self.y.0) / (self.x.1 - self.x.0)).abs() == 1 {
-            Some(
-                Self::get_diagonal_coords((self.x.0, self.x.1))
-                    .into_iter()
-                    .zip(Self::get_diagonal_coords((self.y.0, self.y.1)).into_iter())
-                    .collect(),
-            )
-        } else {
-            None
+    fn parse_point(point: &str) -> anyhow::Result<(isize, isize)> {
+        let (x, y) = point.split_once(',').ok_or_else(|| {
+            anyhow::anyhow!("Expected a point like 'x,y', got '{}'!", point.trim())
+        })?;
+
+        let parse = |coord: &str| {
+            coord
+                .trim()
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid coordinate '{}'!", coord.trim()))
+        };
+
+        Ok((parse(x)?, parse(y)?))
+    }
+
+    fn read_all() -> anyhow::Result<Vec<Self>> {
+        let lines = util::read_lines::<Self>()?;
+
+        match lines.is_empty() {
+            true => Err(anyhow::anyhow!("Missing vent line input!")),
+            false => Ok(lines),
         }
     }
 
-    fn get_diagonal_coords(dir: (isize, isize)) -> Vec<isize> {
-        let is_increasing = dir.0 < dir.1;
+    fn y_min(&self) -> isize {
+        cmp::min(self.y.0, self.y.1)
+    }
+
+    fn y_max(&self) -> isize {
+        cmp::max(self.y.0, self.y.1)
+    }
+
+    fn is_axis_aligned(&self) -> bool {
+        self.x.0 == self.x.1 || self.y.0 == self.y.1
+    }
 
-        (0..=(dir.1 - dir.0).abs())
-            .map(|i| if is_increasing { dir.0 + i } else { dir.0 - i })
-            .collect()
+    /// Whether the line runs at exactly 45 degrees.
+    fn is_diagonal(&self) -> bool {
+        let (dx, dy) = (self.x.1 - self.x.0, self.y.1 - self.y.0);
+
+        dx != 0 && dx.abs() == dy.abs()
+    }
+
+    /// Smallest step between consecutive integer points on the line, found by dividing the
+    /// line's extent by the gcd of its components.
+    fn step(&self) -> (isize, isize) {
+        let (dx, dy) = (self.x.1 - self.x.0, self.y.1 - self.y.0);
+
+        match gcd(dx.unsigned_abs(), dy.unsigned_abs()) as isize {
+            0 => (0, 0),
+            div => (dx / div, dy / div),
+        }
+    }
+
+    /// Range of `x` covered by the line's integer points on row `y`, if it has any there, given
+    /// the line's precomputed [`Line::step`].
+    fn row(&self, y: isize, (step_x, step_y): (isize, isize)) -> Option<(isize, isize)> {
+        if y < self.y_min() || y > self.y_max() {
+            return None;
+        }
+
+        if step_y == 0 {
+            return Some((cmp::min(self.x.0, self.x.1), cmp::max(self.x.0, self.x.1)));
+        }
+
+        match (y - self.y.0) % step_y {
+            0 => Some((
+                self.x.0 + (y - self.y.0) / step_y * step_x,
+                self.x.0 + (y - self.y.0) / step_y * step_x,
+            )),
+            _ => None,
+        }
     }
 }
 
+fn gcd(a: usize, b: usize) -> usize {
+    match b {
+        0 => a,
+        _ => gcd(b, a % b),
+    }
+}
+
+/// Counts the integer points covered by at least two lines.
+///
+/// Rows are swept from top to bottom, keeping only the lines crossing the current row, and each
+/// row's covered ranges are merged by sorting their endpoints. Memory stays proportional to the
+/// number of lines rather than their lengths or the size of the grid.
+fn count_overlaps<'a>(lines: impl IntoIterator<Item = &'a Line>) -> usize {
+    let mut lines = lines.into_iter().collect::<Vec<_>>();
+    lines.sort_unstable_by_key(|line| line.y_min());
+
+    let mut y = match lines.first() {
+        Some(line) => line.y_min(),
+        None => return 0,
+    };
+
+    let mut next = 0;
+    // Lines crossing the current row, along with their steps.
+    let mut active = vec![];
+    let mut bounds = vec![];
+    let mut count = 0;
+
+    loop {
+        while let Some(line) = lines.get(next).filter(|line| line.y_min() <= y) {
+            active.push((*line, line.step()));
+            next += 1;
+        }
+
+        active.retain(|(line, _)| line.y_max() >= y);
+
+        if active.is_empty() {
+            match lines.get(next) {
+                Some(line) => {
+                    y = line.y_min();
+                    continue;
+                }
+                None => break,
+            }
+        }
+
+        bounds.clear();
+        bounds.extend(
+            active
+                .iter()
+                .flat_map(|&(line, step)| line.row(y, step))
+                .flat_map(|(start, end)| [(start, 1), (end + 1, -1)]),
+        );
+
+        count += count_row_overlaps(&mut bounds);
+        y += 1;
+    }
+
+    count
+}
+
+/// Counts the points covered at least twice by ranges given as `(start, 1)` and
+/// `(end + 1, -1)` bounds.
+fn count_row_overlaps(bounds: &mut [(isize, isize)]) -> usize {
+    bounds.sort_unstable();
+
+    let mut depth = 0;
+    let mut prev = 0;
+    let mut count = 0;
+
+    for &(x, delta) in bounds.iter() {
+        if depth >= 2 {
+            count += (x - prev) as usize;
+        }
+
+        depth += delta;
+        prev = x;
+    }
+
+    count
+}
+
 fn part_one(lines: &[Line]) -> anyhow::Result<usize> {
-    let mut coord_count = collections::HashMap::<(isize, isize), usize>::new();
-
-    lines
-        .iter()
-        .flat_map(|line| line.to_coords(false))
-        .flatten()
-        .for_each(|coord| *coord_count.entry(coord).or_insert(0) += 1);
-
-    Ok(coord_count
-        .into_iter()
-        .filter(|&(_, count)| count > 1)
-        .collect::<Vec<_>>()
-        .len())
+    Ok(count_overlaps(
+        lines.iter().filter(|line| line.is_axis_aligned()),
+    ))
 }
 
 fn part_two(lines: &[Line]) -> anyhow::Result<usize> {
-    let mut coord_count = collections::HashMap::<(isize, isize), usize>::new();
-
-    lines
-        .iter()
-        .flat_map(|line| line.to_coords(true))
-        .flatten()
-        .for_each(|coord| *coord_count.entry(coord).or_insert(0) += 1);
-
-    Ok(coord_count
-        .into_iter()
-        .filter(|&(_, count)| count > 1)
-        .collect::<Vec<_>>()
-        .len())
+    Ok(count_overlaps(lines.iter().filter(|line| {
+        line.is_axis_aligned() || line.is_diagonal()
+    })))
 }
 
 fn main() -> anyhow::Result<()> {
-    let lines = util::read_input::<Line>()?;
+    let lines = Line::read_all()?;
+
+    if env::args().nth(1).as_deref() == Some("--any-slope") {
+        println!("Any slope {}", count_overlaps(&lines));
+
+        return Ok(());
+    }
 
     println!("Part one {}", part_one(&lines)?);
     println!("Part two {}", part_two(&lines)?);
@@ -1,79 +1,370 @@
-use std::collections;
+use std::cmp;
+use std::env;
+use std::io;
 use std::str;
 
 extern crate anyhow;
 
-use aoc2021_rust::util;
-
 const FISH_TIMER_RESET: u8 = 6;
 const FISH_TIMER_SPAWN: u8 = 8;
 
+const PART_ONE_DAYS: usize = 80;
+const PART_TWO_DAYS: usize = 256;
+
+/// Timer a fish resets to after spawning, and timer a newborn fish starts with.
+#[derive(Clone, Copy)]
+struct LifeCycle {
+    reset: u8,
+    spawn: u8,
+}
+
+/// Maps the timer counts of one day onto the next, with `cells[to][from]` fish of timer `to`
+/// produced by each fish of timer `from`. Counts are reduced modulo `modulus` when given, and
+/// otherwise fail on overflow rather than wrapping.
+struct TransitionMatrix {
+    cells: Vec<Vec<u128>>,
+    modulus: Option<u128>,
+}
+
 #[derive(Clone)]
 struct FishSchool {
-    num_timers: collections::HashMap<u8, usize>,
+    /// Number of fish per timer state, long enough to hold the life cycle's reset and spawn
+    /// timers as well as every initial timer.
+    num_timers: Vec<usize>,
+    life_cycle: LifeCycle,
+    /// Snapshot of `num_timers` at the start and after every simulated day, if recording.
+    history: Option<Vec<Vec<usize>>>,
 }
 
-impl str::FromStr for FishSchool {
+#[derive(Clone, Copy)]
+enum HistoryFormat {
+    Csv,
+    Json,
+}
+
+impl str::FromStr for HistoryFormat {
     type Err = anyhow::Error;
 
-    fn from_str(input: &str) -> anyhow::Result<Self> {
-        let timers = input
-            .split(',')
-            .map(|timer| timer.parse::<u8>())
-            .collect::<Result<Vec<_>, _>>()?;
+    fn from_str(format: &str) -> anyhow::Result<Self> {
+        match format {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            _ => Err(anyhow::anyhow!(
+                "Unknown history format '{}', expected 'csv' or 'json'!",
+                format
+            )),
+        }
+    }
+}
 
-        Ok(Self {
-            num_timers: (0..=FISH_TIMER_SPAWN)
-                .map(|state| {
-                    (
-                        state,
-                        timers
-                            .iter()
-                            .filter(|&&timer| timer == state)
-                            .collect::<Vec<_>>()
-                            .len(),
-                    )
-                })
+impl Default for LifeCycle {
+    fn default() -> Self {
+        Self {
+            reset: FISH_TIMER_RESET,
+            spawn: FISH_TIMER_SPAWN,
+        }
+    }
+}
+
+impl LifeCycle {
+    fn num_states(&self) -> usize {
+        cmp::max(self.reset, self.spawn) as usize + 1
+    }
+}
+
+impl FishSchool {
+    fn new(timers: &[u8], life_cycle: LifeCycle) -> Self {
+        let mut num_timers = vec![0; life_cycle.num_states()];
+
+        for &timer in timers {
+            if timer as usize >= num_timers.len() {
+                num_timers.resize(timer as usize + 1, 0);
+            }
+
+            num_timers[timer as usize] += 1;
+        }
+
+        Self {
+            num_timers,
+            life_cycle,
+            history: None,
+        }
+    }
+
+    fn with_history(mut self) -> Self {
+        self.history = Some(vec![self.num_timers.clone()]);
+        self
+    }
+
+    fn simulate(&mut self, days: usize) -> anyhow::Result<usize> {
+        (0..days).try_for_each(|_| self.simulate_day())?;
+
+        self.num_timers
+            .iter()
+            .try_fold(0usize, |sum, &num_timer| sum.checked_add(num_timer))
+            .ok_or_else(|| anyhow::anyhow!("Fish population overflowed!"))
+    }
+
+    fn simulate_day(&mut self) -> anyhow::Result<()> {
+        let num_spawning = self.num_timers[0];
+
+        self.num_timers.rotate_left(1);
+
+        if let Some(last) = self.num_timers.last_mut() {
+            *last = 0;
+        }
+
+        for state in [self.life_cycle.reset, self.life_cycle.spawn] {
+            let num_timer = &mut self.num_timers[state as usize];
+
+            *num_timer = num_timer
+                .checked_add(num_spawning)
+                .ok_or_else(|| anyhow::anyhow!("Fish population overflowed!"))?;
+        }
+
+        if let Some(history) = self.history.as_mut() {
+            history.push(self.num_timers.clone());
+        }
+
+        Ok(())
+    }
+
+    fn history_to_string(&self, format: HistoryFormat) -> anyhow::Result<String> {
+        let history = self
+            .history
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Fish school history was not recorded!"))?;
+
+        let mut out = String::new();
+
+        match format {
+            HistoryFormat::Csv => {
+                let timers = (0..self.num_timers.len())
+                    .map(|state| format!("timer_{}", state))
+                    .collect::<Vec<_>>();
+
+                out += &format!("day,{},total\n", timers.join(","));
+
+                for (day, num_timers) in history.iter().enumerate() {
+                    let timers = num_timers.iter().map(usize::to_string).collect::<Vec<_>>();
+                    let total = num_timers.iter().sum::<usize>();
+
+                    out += &format!("{},{},{}\n", day, timers.join(","), total);
+                }
+            }
+            HistoryFormat::Json => {
+                let days = history
+                    .iter()
+                    .enumerate()
+                    .map(|(day, num_timers)| {
+                        let timers = num_timers.iter().map(usize::to_string).collect::<Vec<_>>();
+                        let total = num_timers.iter().sum::<usize>();
+
+                        format!(
+                            "  {{\"day\": {}, \"timers\": [{}], \"total\": {}}}",
+                            day,
+                            timers.join(", "),
+                            total
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                out += &format!("[\n{}\n]\n", days.join(",\n"));
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+impl TransitionMatrix {
+    fn identity(len: usize, modulus: Option<u128>) -> Self {
+        Self {
+            cells: (0..len)
+                .map(|to| (0..len).map(|from| (to == from) as u128).collect())
                 .collect(),
+            modulus,
+        }
+    }
+
+    fn lanternfish(life_cycle: LifeCycle, len: usize, modulus: Option<u128>) -> Self {
+        let mut matrix = Self {
+            cells: vec![vec![0; len]; len],
+            modulus,
+        };
+
+        (1..len).for_each(|state| matrix.cells[state - 1][state] = 1);
+        matrix.cells[life_cycle.reset as usize][0] += 1;
+        matrix.cells[life_cycle.spawn as usize][0] += 1;
+
+        matrix
+    }
+
+    fn add(&self, lhs: u128, rhs: u128) -> anyhow::Result<u128> {
+        match self.modulus {
+            Some(modulus) => Ok((lhs + rhs) % modulus),
+            None => lhs
+                .checked_add(rhs)
+                .ok_or_else(|| anyhow::anyhow!("Fish population overflowed, use a modulus!")),
+        }
+    }
+
+    fn mul(&self, lhs: u128, rhs: u128) -> anyhow::Result<u128> {
+        match self.modulus {
+            Some(modulus) => Ok(lhs * rhs % modulus),
+            None => lhs
+                .checked_mul(rhs)
+                .ok_or_else(|| anyhow::anyhow!("Fish population overflowed, use a modulus!")),
+        }
+    }
+
+    fn matmul(&self, other: &Self) -> anyhow::Result<Self> {
+        let len = self.cells.len();
+        let mut cells = vec![vec![0; len]; len];
+
+        for (row, cells) in cells.iter_mut().enumerate() {
+            for (col, cell) in cells.iter_mut().enumerate() {
+                for idx in 0..len {
+                    let prod = self.mul(self.cells[row][idx], other.cells[idx][col])?;
+                    *cell = self.add(*cell, prod)?;
+                }
+            }
+        }
+
+        Ok(Self {
+            cells,
+            modulus: self.modulus,
         })
     }
+
+    fn pow(self, mut exp: u64) -> anyhow::Result<Self> {
+        let mut acc = Self::identity(self.cells.len(), self.modulus);
+        let mut base = self;
+
+        while exp > 0 {
+            if exp & 1 == 1 {
+                acc = acc.matmul(&base)?;
+            }
+
+            exp >>= 1;
+
+            if exp > 0 {
+                base = base.matmul(&base)?;
+            }
+        }
+
+        Ok(acc)
+    }
 }
 
 impl FishSchool {
-    fn simulate(&mut self, days: usize) -> usize {
-        (0..days).for_each(|_| self.simulate_day());
+    /// Population after `days` days, computed in `O(log days)` by raising the transition matrix
+    /// to the power of `days`. The result is reduced modulo `modulus` when given, which must
+    /// fit in a `u64` so that products of reduced counts fit in a `u128`.
+    fn population(&self, days: u64, modulus: Option<u64>) -> anyhow::Result<u128> {
+        if modulus == Some(0) {
+            return Err(anyhow::anyhow!("Modulus must be positive!"));
+        }
 
-        self.num_timers.values().sum()
-    }
+        let matrix = TransitionMatrix::lanternfish(
+            self.life_cycle,
+            self.num_timers.len(),
+            modulus.map(u128::from),
+        )
+        .pow(days)?;
+        let mut population = 0;
 
-    fn simulate_day(&mut self) -> () {
-        let mut num_timers = collections::HashMap::new();
+        for (from, &num_timer) in self.num_timers.iter().enumerate() {
+            let num_timer = matrix
+                .modulus
+                .map_or(num_timer as u128, |modulus| num_timer as u128 % modulus);
 
-        self.num_timers.iter().for_each(|(&state, &num_timer)| {
-            if state == 0 {
-                *num_timers.entry(FISH_TIMER_RESET).or_insert(0) += num_timer;
-                *num_timers.entry(FISH_TIMER_SPAWN).or_insert(0) += num_timer;
-            } else {
-                *num_timers.entry(state - 1).or_insert(0) += num_timer;
+            for cells in matrix.cells.iter() {
+                let num_fish = matrix.mul(cells[from], num_timer)?;
+                population = matrix.add(population, num_fish)?;
             }
-        });
+        }
 
-        self.num_timers = num_timers;
+        Ok(population)
     }
 }
 
+fn parse_timers(input: &str) -> anyhow::Result<Vec<u8>> {
+    let timers = input
+        .trim()
+        .split(',')
+        .map(|timer| {
+            timer
+                .trim()
+                .parse::<u8>()
+                .map_err(|_| anyhow::anyhow!("Invalid fish timer '{}'!", timer))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(timers)
+}
+
 fn part_one(mut school: FishSchool) -> anyhow::Result<usize> {
-    Ok(school.simulate(80))
+    school.simulate(PART_ONE_DAYS)
 }
 
 fn part_two(mut school: FishSchool) -> anyhow::Result<usize> {
-    Ok(school.simulate(256))
+    school.simulate(PART_TWO_DAYS)
 }
 
 fn main() -> anyhow::Result<()> {
-    let school = util::read_input::<FishSchool>()?
-        .pop()
-        .ok_or_else(|| anyhow::anyhow!("Unexpected empty set of initial states!"))?;
+    let mut args = env::args().skip(1);
+    let mut days = None;
+    let mut modulus = None;
+    let mut life_cycle = LifeCycle::default();
+    let mut history = None;
+
+    while let Some(arg) = args.next() {
+        let val = args
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Missing value for argument '{}'!", arg))?;
+
+        match arg.as_str() {
+            "--days" => days = Some(val.parse::<u64>()?),
+            "--modulus" => modulus = Some(val.parse::<u64>()?),
+            "--reset" => life_cycle.reset = val.parse()?,
+            "--spawn" => life_cycle.spawn = val.parse()?,
+            "--history" => history = Some(val.parse::<HistoryFormat>()?),
+            _ => return Err(anyhow::anyhow!("Unknown argument '{}'!", arg)),
+        }
+    }
+
+    let input = io::read_to_string(io::stdin())?;
+
+    if input.trim().is_empty() {
+        return Err(anyhow::anyhow!("Unexpected empty set of initial states!"));
+    }
+
+    let school = FishSchool::new(&parse_timers(&input)?, life_cycle);
+
+    if let Some(format) = history {
+        if modulus.is_some() {
+            return Err(anyhow::anyhow!(
+                "A modulus cannot be used with '--history'!"
+            ));
+        }
+
+        let mut school = school.with_history();
+        let days = days.map_or(Ok(PART_TWO_DAYS), usize::try_from)?;
+
+        school.simulate(days)?;
+        print!("{}", school.history_to_string(format)?);
+
+        return Ok(());
+    }
+
+    if let Some(days) = days {
+        println!("Day {}: {}", days, school.population(days, modulus)?);
+
+        return Ok(());
+    } else if modulus.is_some() {
+        return Err(anyhow::anyhow!("A modulus requires '--days'!"));
+    }
 
     println!("Part one: {}", part_one(school.clone())?);
     println!("Part two: {}", part_two(school)?);
@@ -1,4 +1,7 @@
 use std::env;
+use std::fs;
+use std::io;
+use std::ops;
 use std::str;
 
 extern crate anyhow;
@@ -9,53 +12,189 @@ impl str::FromStr for Crabs {
     type Err = anyhow::Error;
 
     fn from_str(input: &str) -> anyhow::Result<Self> {
-        Ok(Crabs(
-            input
-                .split(',')
-                .map(|pos| pos.parse())
-                .collect::<Result<Vec<_>, _>>()?,
-        ))
+        let crabs = input
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|pos| !pos.is_empty())
+            .map(|pos| {
+                pos.parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid crab position '{}'!", pos))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        match crabs.is_empty() {
+            true => Err(anyhow::anyhow!("Unexpected empty set of crab positions!")),
+            false => Ok(Crabs(crabs)),
+        }
     }
 }
 
-fn part_one(crabs: &Crabs) -> Option<usize> {
-    let max_pos = *crabs.0.iter().max()?;
+/// Fuel a crab burns to move a given distance.
+trait FuelCost {
+    fn cost(&self, dist: usize) -> usize;
+
+    /// Positions guaranteed to include an optimal one. Defaults to every position between the
+    /// outermost crabs, which holds for any cost that never decreases with distance.
+    fn candidates(&self, crabs: &Crabs) -> Option<ops::RangeInclusive<isize>> {
+        Some(*crabs.0.iter().min()?..=*crabs.0.iter().max()?)
+    }
+}
+
+/// One unit of fuel per step.
+struct Linear;
+
+/// One more unit of fuel for each step than the last, i.e. `d * (d + 1) / 2`.
+struct Triangular;
+
+/// The square of the distance.
+struct Quadratic;
 
-    (0..=max_pos)
-        .map(|pos| crabs.0.iter().map(|crab| (pos - crab).abs() as usize).sum())
-        .min()
+impl<F: Fn(usize) -> usize> FuelCost for F {
+    fn cost(&self, dist: usize) -> usize {
+        self(dist)
+    }
 }
 
-fn part_two(crabs: &Crabs) -> Option<usize> {
-    let max_pos = *crabs.0.iter().max()?;
+impl FuelCost for Linear {
+    fn cost(&self, dist: usize) -> usize {
+        dist
+    }
+
+    fn candidates(&self, crabs: &Crabs) -> Option<ops::RangeInclusive<isize>> {
+        let pos = crabs.median()?;
 
-    (0..=max_pos)
-        .map(|pos| {
-            crabs
-                .0
-                .iter()
-                .map(|crab| (1..=(pos - crab).abs() as usize).sum::<usize>())
-                .sum()
-        })
-        .min()
+        Some(pos..=pos)
+    }
+}
+
+impl FuelCost for Triangular {
+    fn cost(&self, dist: usize) -> usize {
+        dist * (dist + 1) / 2
+    }
+
+    /// The cost is convex and its real-valued minimum lies within half a unit of the mean, so
+    /// the integer minimum is at most one past the mean's floor or ceiling.
+    fn candidates(&self, crabs: &Crabs) -> Option<ops::RangeInclusive<isize>> {
+        let (floor, ceil) = crabs.mean()?;
+
+        Some(floor - 1..=ceil + 1)
+    }
+}
+
+impl FuelCost for Quadratic {
+    fn cost(&self, dist: usize) -> usize {
+        dist * dist
+    }
+
+    /// The sum of squared distances is minimised exactly at the mean.
+    fn candidates(&self, crabs: &Crabs) -> Option<ops::RangeInclusive<isize>> {
+        let (floor, ceil) = crabs.mean()?;
+
+        Some(floor..=ceil)
+    }
+}
+
+impl str::FromStr for Box<dyn FuelCost> {
+    type Err = anyhow::Error;
+
+    fn from_str(cost: &str) -> anyhow::Result<Self> {
+        match cost {
+            "linear" => Ok(Box::new(Linear)),
+            "triangular" => Ok(Box::new(Triangular)),
+            "quadratic" => Ok(Box::new(Quadratic)),
+            _ => Err(anyhow::anyhow!(
+                "Unknown fuel cost '{}', expected 'linear', 'triangular' or 'quadratic'!",
+                cost
+            )),
+        }
+    }
+}
+
+impl Crabs {
+    fn fuel(&self, pos: isize, cost: &dyn FuelCost) -> usize {
+        self.0
+            .iter()
+            .map(|crab| cost.cost((pos - crab).unsigned_abs()))
+            .sum()
+    }
+
+    /// Finds the position needing the least fuel under `cost`, along with that fuel, preferring
+    /// the leftmost position on ties.
+    fn minimise(&self, cost: &dyn FuelCost) -> Option<(isize, usize)> {
+        cost.candidates(self)?
+            .map(|pos| (pos, self.fuel(pos, cost)))
+            .min_by_key(|&(_, fuel)| fuel)
+    }
+
+    /// Any median minimises the sum of distances, so the lower median is taken.
+    fn median(&self) -> Option<isize> {
+        let mut crabs = self.0.clone();
+        let mid = crabs.len().checked_sub(1)? / 2;
+
+        Some(*crabs.select_nth_unstable(mid).1)
+    }
+
+    /// Floor and ceiling of the mean position.
+    fn mean(&self) -> Option<(isize, isize)> {
+        let len = isize::try_from(self.0.len()).ok().filter(|&len| len > 0)?;
+        let sum = self.0.iter().sum::<isize>();
+
+        Some((sum.div_euclid(len), -(-sum).div_euclid(len)))
+    }
+}
+
+fn part_one(crabs: &Crabs) -> Option<(isize, usize)> {
+    crabs.minimise(&Linear)
+}
+
+fn part_two(crabs: &Crabs) -> Option<(isize, usize)> {
+    crabs.minimise(&Triangular)
 }
 
 fn main() -> anyhow::Result<()> {
-    let crabs = env::args()
-        .collect::<Vec<_>>()
-        .pop()
-        .ok_or_else(|| anyhow::anyhow!("Unexpected empty set of crab positions!"))?
-        .parse::<Crabs>()?;
-
-    println!(
-        "Part one: {}",
-        part_one(&crabs).ok_or_else(|| anyhow::anyhow!("No answer found!"))?
-    );
-
-    println!(
-        "Part two: {}",
-        part_two(&crabs).ok_or_else(|| anyhow::anyhow!("No answer found!"))?
-    );
+    let mut args = env::args().skip(1);
+    let mut cost = None;
+    let mut path = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--cost" => {
+                cost = Some(
+                    args.next()
+                        .ok_or_else(|| anyhow::anyhow!("Missing fuel cost model!"))?
+                        .parse::<Box<dyn FuelCost>>()?,
+                )
+            }
+            _ if arg.starts_with("--") => {
+                return Err(anyhow::anyhow!("Unknown argument '{}'!", arg))
+            }
+            _ if path.is_none() => path = Some(arg),
+            _ => return Err(anyhow::anyhow!("Expected '[--cost <model>] [<file>]'!")),
+        }
+    }
+
+    let input = match path {
+        Some(path) => fs::read_to_string(&path)
+            .map_err(|err| anyhow::anyhow!("Failed to read '{}': {}", path, err))?,
+        None => io::read_to_string(io::stdin())?,
+    };
+
+    let crabs = input.parse::<Crabs>()?;
+
+    if let Some(cost) = cost {
+        let (pos, fuel) = crabs
+            .minimise(cost.as_ref())
+            .ok_or_else(|| anyhow::anyhow!("No answer found!"))?;
+
+        println!("Fuel: {} (position {})", fuel, pos);
+
+        return Ok(());
+    }
+
+    let (pos, fuel) = part_one(&crabs).ok_or_else(|| anyhow::anyhow!("No answer found!"))?;
+    println!("Part one: {} (position {})", fuel, pos);
+
+    let (pos, fuel) = part_two(&crabs).ok_or_else(|| anyhow::anyhow!("No answer found!"))?;
+    println!("Part two: {} (position {})", fuel, pos);
 
     Ok(())
 }
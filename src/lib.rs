@@ -1 +1,3 @@
+pub mod bingo;
+pub mod submarine;
 pub mod util;
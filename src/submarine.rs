@@ -0,0 +1,180 @@
+use std::str;
+
+extern crate anyhow;
+
+use crate::util;
+
+const FORWARD: &str = "forward";
+const BACK: &str = "back";
+const UP: &str = "up";
+const DOWN: &str = "down";
+const RESET: &str = "reset";
+const SET: &str = "set";
+const AIM: &str = "aim";
+
+const COMMENT: char = '#';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Forward(isize),
+    Back(isize),
+    Up(isize),
+    Down(isize),
+    /// Returns the submarine to the surface at its starting point with zero aim.
+    Reset,
+    SetAim(isize),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Position {
+    pub pos: isize,
+    pub depth: isize,
+    pub aim: isize,
+}
+
+/// How a movement command changes the submarine's position. `Reset` and `SetAim` behave the same
+/// under every model and are handled by the `Interpreter`.
+pub trait MovementModel {
+    fn apply(&self, position: &mut Position, command: &Command);
+}
+
+/// `up` and `down` change the depth directly.
+pub struct Direct;
+
+/// `up` and `down` change the aim, and moving horizontally changes the depth by the aim times the
+/// distance moved.
+pub struct Aimed;
+
+pub struct Interpreter<M: MovementModel> {
+    model: M,
+    position: Position,
+    /// The starting position followed by the position after every command, if recording.
+    log: Option<Vec<Position>>,
+}
+
+impl str::FromStr for Command {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> anyhow::Result<Self> {
+        let input = input.to_lowercase();
+
+        match input.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [FORWARD, units] => Ok(Self::Forward(units.parse()?)),
+            [BACK, units] => Ok(Self::Back(units.parse()?)),
+            [UP, units] => Ok(Self::Up(units.parse()?)),
+            [DOWN, units] => Ok(Self::Down(units.parse()?)),
+            [RESET] => Ok(Self::Reset),
+            [SET, AIM, aim] => Ok(Self::SetAim(aim.parse()?)),
+            [FORWARD | BACK | UP | DOWN] | [SET, AIM] => Err(anyhow::anyhow!("Missing units!")),
+            _ => Err(anyhow::anyhow!("Unknown instruction '{}'!", input.trim())),
+        }
+    }
+}
+
+/// Parses one command per line, skipping blank lines and anything after a `#`.
+pub fn parse_script(script: &str) -> anyhow::Result<Vec<Command>> {
+    util::parse_lines(
+        script
+            .lines()
+            .map(|line| line.split(COMMENT).next().unwrap_or_default()),
+    )
+}
+
+impl Position {
+    pub fn result(&self) -> isize {
+        self.pos * self.depth
+    }
+}
+
+impl MovementModel for Direct {
+    fn apply(&self, position: &mut Position, command: &Command) {
+        match *command {
+            Command::Forward(units) => position.pos += units,
+            Command::Back(units) => position.pos -= units,
+            Command::Up(units) => position.depth -= units,
+            Command::Down(units) => position.depth += units,
+            Command::Reset | Command::SetAim(_) => (),
+        }
+    }
+}
+
+impl MovementModel for Aimed {
+    fn apply(&self, position: &mut Position, command: &Command) {
+        match *command {
+            Command::Forward(units) => {
+                position.pos += units;
+                position.depth += position.aim * units;
+            }
+            Command::Back(units) => {
+                position.pos -= units;
+                position.depth -= position.aim * units;
+            }
+            Command::Up(units) => position.aim -= units,
+            Command::Down(units) => position.aim += units,
+            Command::Reset | Command::SetAim(_) => (),
+        }
+    }
+}
+
+impl<M: MovementModel> Interpreter<M> {
+    pub fn new(model: M) -> Self {
+        Self {
+            model,
+            position: Position::default(),
+            log: None,
+        }
+    }
+
+    pub fn with_log(mut self) -> Self {
+        self.log = Some(vec![self.position]);
+        self
+    }
+
+    pub fn log(&self) -> Option<&[Position]> {
+        self.log.as_deref()
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    pub fn execute(&mut self, command: &Command) {
+        match *command {
+            Command::Reset => self.position = Position::default(),
+            Command::SetAim(aim) => self.position.aim = aim,
+            _ => self.model.apply(&mut self.position, command),
+        }
+
+        if let Some(log) = self.log.as_mut() {
+            log.push(self.position);
+        }
+    }
+
+    pub fn run(&mut self, commands: &[Command]) -> &Position {
+        commands.iter().for_each(|command| self.execute(command));
+
+        &self.position
+    }
+}
+
+pub fn log_to_csv(log: &[Position]) -> String {
+    let mut csv = String::from("step,pos,depth,aim\n");
+
+    for (step, position) in log.iter().enumerate() {
+        csv += &format!(
+            "{},{},{},{}\n",
+            step, position.pos, position.depth, position.aim
+        );
+    }
+
+    csv
+}
+
+pub fn max_depth(log: &[Position]) -> Option<isize> {
+    log.iter().map(|position| position.depth).max()
+}
+
+/// Whether the submarine ever rose above the surface, i.e. reached a negative depth.
+pub fn went_above_surface(log: &[Position]) -> bool {
+    log.iter().any(|position| position.depth < 0)
+}
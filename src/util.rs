@@ -16,3 +16,29 @@ pub fn read_input<T: str::FromStr>() -> anyhow::Result<Vec<T>> {
 
     Ok(input)
 }
+
+/// Parses one value per line, skipping blank lines and tagging errors with their line number.
+pub fn parse_lines<'a, T>(lines: impl IntoIterator<Item = &'a str>) -> anyhow::Result<Vec<T>>
+where
+    T: str::FromStr,
+    T::Err: Into<anyhow::Error>,
+{
+    lines
+        .into_iter()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            line.parse()
+                .map_err(|err: T::Err| err.into().context(format!("Line {}", idx + 1)))
+        })
+        .collect()
+}
+
+/// Reads stdin with [`parse_lines`].
+pub fn read_lines<T>() -> anyhow::Result<Vec<T>>
+where
+    T: str::FromStr,
+    T::Err: Into<anyhow::Error>,
+{
+    parse_lines(io::read_to_string(io::stdin())?.lines())
+}